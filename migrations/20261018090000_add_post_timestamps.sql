@@ -0,0 +1,32 @@
+-- RedefineTables
+-- SQLite cannot add columns with non-constant defaults, so rebuild the table instead
+CREATE TABLE "new_Post" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "title" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "published" BOOLEAN NOT NULL DEFAULT false,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    "updated_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    "published_at" DATETIME
+);
+
+INSERT INTO
+    "new_Post" ("id", "title", "body", "published", "published_at")
+SELECT
+    "id",
+    "title",
+    "body",
+    "published",
+    CASE
+        WHEN "published" THEN strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+        ELSE NULL
+    END
+FROM
+    "Post";
+
+DROP TABLE "Post";
+
+ALTER TABLE "new_Post" RENAME TO "Post";
+
+-- CreateIndex
+CREATE INDEX "Post_published_published_at_idx" ON "Post" ("published", "published_at");
@@ -0,0 +1,46 @@
+-- RedefineTables
+-- SQLite cannot add a NOT NULL column without a default, so rebuild the table instead
+CREATE TABLE "new_Post" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "title" TEXT NOT NULL,
+    "slug" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "published" BOOLEAN NOT NULL DEFAULT false,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    "updated_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    "published_at" DATETIME
+);
+
+-- existing posts get an `id`-based slug, which is guaranteed to be unique
+INSERT INTO
+    "new_Post" (
+        "id",
+        "title",
+        "slug",
+        "body",
+        "published",
+        "created_at",
+        "updated_at",
+        "published_at"
+    )
+SELECT
+    "id",
+    "title",
+    'post-' || "id",
+    "body",
+    "published",
+    "created_at",
+    "updated_at",
+    "published_at"
+FROM
+    "Post";
+
+DROP TABLE "Post";
+
+ALTER TABLE "new_Post" RENAME TO "Post";
+
+-- CreateIndex
+CREATE INDEX "Post_published_published_at_idx" ON "Post" ("published", "published_at");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Post_slug_key" ON "Post" ("slug");
@@ -0,0 +1,49 @@
+-- CreateTable
+-- External content FTS5 index over post titles and bodies, kept in sync by the triggers below
+CREATE VIRTUAL TABLE "PostSearch" USING fts5 (
+    "title",
+    "body",
+    content = 'Post',
+    content_rowid = 'id',
+    tokenize = 'porter unicode61'
+);
+
+-- CreateTrigger
+CREATE TRIGGER "Post_search_insert" AFTER INSERT ON "Post" BEGIN
+INSERT INTO
+    "PostSearch" ("rowid", "title", "body")
+VALUES
+    (new."id", new."title", new."body");
+
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "Post_search_delete" AFTER DELETE ON "Post" BEGIN
+INSERT INTO
+    "PostSearch" ("PostSearch", "rowid", "title", "body")
+VALUES
+    ('delete', old."id", old."title", old."body");
+
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "Post_search_update" AFTER
+UPDATE OF "title",
+"body" ON "Post" BEGIN
+INSERT INTO
+    "PostSearch" ("PostSearch", "rowid", "title", "body")
+VALUES
+    ('delete', old."id", old."title", old."body");
+
+INSERT INTO
+    "PostSearch" ("rowid", "title", "body")
+VALUES
+    (new."id", new."title", new."body");
+
+END;
+
+-- index any existing posts
+INSERT INTO
+    "PostSearch" ("PostSearch")
+VALUES
+    ('rebuild');
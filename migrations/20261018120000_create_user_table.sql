@@ -0,0 +1,14 @@
+-- CreateTable
+CREATE TABLE "User" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "name" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- AddColumn
+-- existing posts have no recorded author
+ALTER TABLE "Post"
+ADD COLUMN "author_id" INTEGER REFERENCES "User" ("id") ON DELETE SET NULL;
+
+-- CreateIndex
+CREATE INDEX "Post_author_id_idx" ON "Post" ("author_id");
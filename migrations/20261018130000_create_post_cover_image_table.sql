@@ -0,0 +1,10 @@
+-- CreateTable
+-- files are named by content hash, so several posts may share a `filename`
+CREATE TABLE "PostCoverImage" (
+    "post_id" INTEGER NOT NULL PRIMARY KEY REFERENCES "Post" ("id") ON DELETE CASCADE,
+    "filename" TEXT NOT NULL,
+    "content_type" TEXT NOT NULL,
+    "width" INTEGER NOT NULL,
+    "height" INTEGER NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,20 @@
+-- CreateTable
+-- `name` is stored normalised to a slug
+CREATE TABLE "Tag" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "name" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Tag_name_key" ON "Tag" ("name");
+
+-- CreateTable
+CREATE TABLE "PostTag" (
+    "post_id" INTEGER NOT NULL REFERENCES "Post" ("id") ON DELETE CASCADE,
+    "tag_id" INTEGER NOT NULL REFERENCES "Tag" ("id") ON DELETE CASCADE,
+    PRIMARY KEY ("post_id", "tag_id")
+);
+
+-- CreateIndex
+CREATE INDEX "PostTag_tag_id_idx" ON "PostTag" ("tag_id");
@@ -0,0 +1,15 @@
+-- CreateTable
+CREATE TABLE "Comment" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "post_id" INTEGER NOT NULL REFERENCES "Post" ("id") ON DELETE CASCADE,
+    "author_name" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'pending' CHECK ("status" IN ('pending', 'approved', 'rejected')),
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- CreateIndex
+CREATE INDEX "Comment_post_id_status_idx" ON "Comment" ("post_id", "status");
+
+-- CreateIndex
+CREATE INDEX "Comment_status_idx" ON "Comment" ("status");
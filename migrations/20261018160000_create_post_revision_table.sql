@@ -0,0 +1,43 @@
+-- CreateTable
+-- Snapshot of post content, recorded by the triggers below whenever the title or body changes
+CREATE TABLE "PostRevision" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "post_id" INTEGER NOT NULL REFERENCES "Post" ("id") ON DELETE CASCADE,
+    "title" TEXT NOT NULL,
+    "body" TEXT NOT NULL,
+    "created_at" DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- CreateIndex
+CREATE INDEX "PostRevision_post_id_idx" ON "PostRevision" ("post_id");
+
+-- CreateTrigger
+CREATE TRIGGER "Post_revision_insert" AFTER INSERT ON "Post" BEGIN
+INSERT INTO
+    "PostRevision" ("post_id", "title", "body")
+VALUES
+    (new."id", new."title", new."body");
+
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "Post_revision_update" AFTER
+UPDATE OF "title",
+"body" ON "Post" WHEN old."title" IS NOT new."title"
+OR old."body" IS NOT new."body" BEGIN
+INSERT INTO
+    "PostRevision" ("post_id", "title", "body")
+VALUES
+    (new."id", new."title", new."body");
+
+END;
+
+-- record the current content of any existing posts
+INSERT INTO
+    "PostRevision" ("post_id", "title", "body")
+SELECT
+    "id",
+    "title",
+    "body"
+FROM
+    "Post";
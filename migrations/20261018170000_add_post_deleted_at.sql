@@ -0,0 +1,7 @@
+-- AddColumn
+-- deleted drafts are kept in the trash until purged, so accidental deletions can be restored
+ALTER TABLE "Post"
+ADD COLUMN "deleted_at" DATETIME;
+
+-- CreateIndex
+CREATE INDEX "Post_deleted_at_idx" ON "Post" ("deleted_at");
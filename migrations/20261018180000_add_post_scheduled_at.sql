@@ -0,0 +1,7 @@
+-- AddColumn
+-- drafts with a scheduled time are published by a background task once the time has passed
+ALTER TABLE "Post"
+ADD COLUMN "scheduled_at" DATETIME;
+
+-- CreateIndex
+CREATE INDEX "Post_scheduled_at_idx" ON "Post" ("scheduled_at");
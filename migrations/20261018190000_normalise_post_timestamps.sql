@@ -0,0 +1,15 @@
+-- NormaliseColumns
+-- scheduled times were stored as sent, so could use any offset or precision, and are copied to
+-- `published_at` on publishing.  Store both in the same format as the other timestamps, so they
+-- compare correctly as text.
+UPDATE "Post"
+SET
+    "published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', "published_at")
+WHERE
+    "published_at" IS NOT NULL;
+
+UPDATE "Post"
+SET
+    "scheduled_at" = strftime('%Y-%m-%dT%H:%M:%fZ', "scheduled_at")
+WHERE
+    "scheduled_at" IS NOT NULL;
@@ -0,0 +1,351 @@
+use std::{fmt, path::Path, str::FromStr, sync::Arc};
+
+use anyhow::Context as _;
+use async_graphql::ErrorExtensions;
+use axum::http::{HeaderMap, header};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, jwk::JwkSet};
+use serde::Deserialize;
+
+/// Role granted to a viewer through the `roles` claim of their bearer token
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// May see every draft
+    Admin,
+
+    /// May publish posts and delete drafts
+    Editor,
+}
+
+/// Identity of the caller making a GraphQL request, inserted into the request data by the GraphQL
+/// handler
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Viewer {
+    /// Caller did not present a bearer token
+    #[default]
+    Anonymous,
+
+    /// Caller presented a valid bearer token, issued to `subject` and granting `roles`
+    Authenticated { subject: String, roles: Vec<Role> },
+}
+
+impl Viewer {
+    /// Returns the token subject for an authenticated viewer, or `None` for an anonymous one
+    #[must_use]
+    pub fn subject(&self) -> Option<&str> {
+        match self {
+            Self::Anonymous => None,
+            Self::Authenticated { subject, .. } => Some(subject),
+        }
+    }
+
+    /// Returns the id of the user the viewer is signed in as, when the token subject is a user id
+    #[must_use]
+    pub fn user_id(&self) -> Option<i64> {
+        self.subject().and_then(|value| value.parse().ok())
+    }
+
+    /// Returns `true` if the viewer is authenticated and was granted `role`
+    #[must_use]
+    pub fn has_role(&self, role: Role) -> bool {
+        match self {
+            Self::Anonymous => false,
+            Self::Authenticated { roles, .. } => roles.contains(&role),
+        }
+    }
+}
+
+/// Reasons a caller may be denied access, reported to GraphQL clients with an error extension
+/// `code` of `UNAUTHENTICATED` or `FORBIDDEN`
+#[derive(Debug, PartialEq, Eq)]
+pub enum AuthError {
+    /// No bearer token was presented for an operation which needs one
+    Unauthenticated,
+
+    /// A bearer token was presented, but it is malformed, expired or not signed by a trusted key
+    InvalidToken,
+
+    /// Viewer is authenticated, but lacks the role or ownership the operation needs
+    Forbidden,
+}
+
+impl AuthError {
+    /// Error extension code reported to GraphQL clients
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unauthenticated | Self::InvalidToken => "UNAUTHENTICATED",
+            Self::Forbidden => "FORBIDDEN",
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthenticated => f.write_str("Authentication is required"),
+            Self::InvalidToken => f.write_str("Bearer token is invalid or has expired"),
+            Self::Forbidden => f.write_str("Viewer is not permitted to perform this operation"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl ErrorExtensions for AuthError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, extensions| {
+            extensions.set("code", self.code());
+        })
+    }
+}
+
+/// Claims read from a bearer token.  `exp` is also required, and checked during validation.
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+
+    /// Role names, where any not recognised by this service are ignored
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// Key trusted to sign bearer tokens, along with the only algorithm accepted for it
+struct TrustedKey {
+    key_id: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+/// Validates bearer tokens against either an HS256 shared secret, or the keys in a local JWKS
+/// file.  With no keys configured, every request is treated as anonymous and any bearer token is
+/// rejected.
+#[derive(Clone, Default)]
+pub struct Authenticator {
+    keys: Arc<[TrustedKey]>,
+}
+
+impl Authenticator {
+    /// Trust tokens signed with HS256, using `secret`
+    #[must_use]
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self {
+            keys: Arc::new([TrustedKey {
+                key_id: None,
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(secret),
+            }]),
+        }
+    }
+
+    /// Trust tokens signed with any key in the JWKS file at `path`.  Each key must declare its
+    /// algorithm (`alg`), and tokens are only accepted when signed with that algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file cannot be read, is not a valid JWK set, or contains a key without a
+    /// supported algorithm.
+    pub fn from_jwks_file<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read(path).with_context(|| format!("read JWKS file `{}`", path.display()))?;
+        let jwks: JwkSet = serde_json::from_slice(&contents)
+            .with_context(|| format!("parse JWKS file `{}`", path.display()))?;
+
+        let keys = jwks
+            .keys
+            .iter()
+            .map(|jwk| {
+                let key_id = jwk.common.key_id.clone();
+                let algorithm = jwk
+                    .common
+                    .key_algorithm
+                    .with_context(|| format!("JWK `{key_id:?}` should declare `alg`"))
+                    .and_then(|value| Ok(Algorithm::from_str(&value.to_string())?))?;
+                let key = DecodingKey::from_jwk(jwk)?;
+
+                Ok(TrustedKey {
+                    key_id,
+                    algorithm,
+                    key,
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(Self {
+            keys: Arc::from(keys),
+        })
+    }
+
+    /// Configure from the environment, using the JWKS file at `JWT_JWKS_FILE` if set, otherwise
+    /// the HS256 secret in `JWT_SECRET`.  Returns an authenticator with no trusted keys if neither
+    /// is set.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `JWT_JWKS_FILE` is set, but the file cannot be loaded.
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        if let Ok(path) = std::env::var("JWT_JWKS_FILE") {
+            return Self::from_jwks_file(path);
+        }
+        if let Ok(secret) = std::env::var("JWT_SECRET") {
+            return Ok(Self::from_secret(secret.as_bytes()));
+        }
+        tracing::warn!(
+            "Neither `JWT_JWKS_FILE` nor `JWT_SECRET` set, so bearer tokens are rejected"
+        );
+
+        Ok(Self::default())
+    }
+
+    /// Identify the caller from the `Authorization` header in `headers`.  Callers without the
+    /// header are anonymous.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the header is present, but does not hold a valid bearer token.
+    pub fn viewer(&self, headers: &HeaderMap) -> Result<Viewer, AuthError> {
+        let Some(value) = headers.get(header::AUTHORIZATION) else {
+            return Ok(Viewer::Anonymous);
+        };
+
+        self.authorization_viewer(value.to_str().map_err(|_| AuthError::InvalidToken)?)
+    }
+
+    /// Identify the caller from an `Authorization` value, such as `Bearer <token>`, where the
+    /// scheme is matched case-insensitively, following RFC 7235
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value does not hold a valid bearer token.
+    pub fn authorization_viewer(&self, authorization: &str) -> Result<Viewer, AuthError> {
+        let token = authorization
+            .split_once(' ')
+            .and_then(|(scheme, token)| scheme.eq_ignore_ascii_case("Bearer").then_some(token))
+            .ok_or(AuthError::InvalidToken)?;
+
+        self.authenticate(token.trim())
+    }
+
+    /// Validate `token`, returning the viewer it was issued to
+    ///
+    /// # Errors
+    ///
+    /// Errors if the token is malformed, expired or not signed by a trusted key.
+    pub fn authenticate(&self, token: &str) -> Result<Viewer, AuthError> {
+        let token_header = decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+        let trusted_key = self
+            .keys
+            .iter()
+            .find(|trusted_key| {
+                trusted_key.algorithm == token_header.alg
+                    && (trusted_key.key_id.is_none() || trusted_key.key_id == token_header.kid)
+            })
+            .ok_or(AuthError::InvalidToken)?;
+
+        let claims = decode::<Claims>(
+            token,
+            &trusted_key.key,
+            &Validation::new(trusted_key.algorithm),
+        )
+        .map_err(|err| {
+            tracing::info!("Rejected bearer token: {err}");
+            AuthError::InvalidToken
+        })?
+        .claims;
+
+        let roles = claims
+            .roles
+            .into_iter()
+            .filter_map(|value| {
+                serde_json::from_value::<Role>(serde_json::Value::String(value)).ok()
+            })
+            .collect();
+
+        Ok(Viewer::Authenticated {
+            subject: claims.sub,
+            roles,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::json;
+
+    use super::{AuthError, Authenticator, Role, Viewer};
+
+    fn token(secret: &[u8], exp: i64) -> String {
+        encode(
+            &Header::default(),
+            &json!({ "sub": "42", "exp": exp, "roles": ["editor", "publisher"] }),
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn authenticate_accepts_token_signed_with_secret_ignoring_unknown_roles() {
+        // arrange
+        let authenticator = Authenticator::from_secret(b"secret");
+        let exp = chrono::Utc::now().timestamp() + 60;
+
+        // act
+        let outcome = authenticator.authenticate(&token(b"secret", exp));
+
+        // assert
+        assert_eq!(
+            outcome,
+            Ok(Viewer::Authenticated {
+                subject: String::from("42"),
+                roles: vec![Role::Editor]
+            })
+        );
+    }
+
+    #[test]
+    fn authenticate_rejects_token_signed_with_other_secret_or_expired() {
+        // arrange
+        let authenticator = Authenticator::from_secret(b"secret");
+        let now = chrono::Utc::now().timestamp();
+
+        // act
+        let wrong_secret = authenticator.authenticate(&token(b"other", now + 60));
+        let expired = authenticator.authenticate(&token(b"secret", now - 3_600));
+
+        // assert
+        assert_eq!(wrong_secret, Err(AuthError::InvalidToken));
+        assert_eq!(expired, Err(AuthError::InvalidToken));
+    }
+
+    #[test]
+    fn authenticate_rejects_every_token_with_no_keys_configured() {
+        // arrange
+        let authenticator = Authenticator::default();
+        let exp = chrono::Utc::now().timestamp() + 60;
+
+        // act
+        let outcome = authenticator.authenticate(&token(b"secret", exp));
+
+        // assert
+        assert_eq!(outcome, Err(AuthError::InvalidToken));
+    }
+
+    #[test]
+    fn authorization_viewer_matches_bearer_scheme_case_insensitively() {
+        // arrange
+        let authenticator = Authenticator::from_secret(b"secret");
+        let token = token(b"secret", chrono::Utc::now().timestamp() + 60);
+
+        // act
+        let lowercase = authenticator.authorization_viewer(&format!("bearer {token}"));
+        let uppercase = authenticator.authorization_viewer(&format!("BEARER {token}"));
+        let basic = authenticator.authorization_viewer(&format!("Basic {token}"));
+
+        // assert
+        assert!(matches!(lowercase, Ok(Viewer::Authenticated { .. })));
+        assert!(matches!(uppercase, Ok(Viewer::Authenticated { .. })));
+        assert_eq!(basic, Err(AuthError::InvalidToken));
+    }
+}
@@ -1,7 +1,41 @@
+use std::time::Instant;
+
+use opentelemetry::KeyValue;
 use sqlx::{
-    Sqlite, SqlitePool,
+    Sqlite, SqliteConnection, SqlitePool,
     migrate::{MigrateDatabase, Migrator},
 };
+use tracing::{Instrument, field::Empty};
+
+use crate::observability::metrics::DatabaseMetrics;
+
+/// Database query, identified in metrics and traces by [`observe_query`]
+#[derive(Clone, Copy, Debug)]
+pub struct DatabaseQuery {
+    /// Name metrics are labelled with, matching the span of the function running the query
+    pub name: &'static str,
+
+    /// SQL operation, such as `SELECT`
+    pub operation: &'static str,
+
+    /// Table the query operates on
+    pub collection: &'static str,
+}
+
+impl DatabaseQuery {
+    #[must_use]
+    pub const fn new(
+        name: &'static str,
+        operation: &'static str,
+        collection: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            operation,
+            collection,
+        }
+    }
+}
 
 /// Create a new `SQLite` database if one does not already exist.
 ///
@@ -36,6 +70,112 @@ pub async fn run_migrations(db_pool: &SqlitePool) {
     }
 }
 
+/// Run `run` on a connection acquired from `db_pool`, recording the query duration, and any error,
+/// labelled by `query` name, along with pool size, idle connections and the time taken to acquire
+/// the connection.  The query runs in a client span carrying the OpenTelemetry semantic-convention
+/// `db.*` attributes.
+///
+/// # Errors
+///
+/// Errors if unable to acquire a connection, or if `run` fails.
+pub async fn observe_query<T>(
+    db_pool: &SqlitePool,
+    query: DatabaseQuery,
+    run: impl AsyncFnOnce(&mut SqliteConnection) -> Result<T, sqlx::Error>,
+) -> Result<T, sqlx::Error> {
+    let summary = format!("{} {}", query.operation, query.collection);
+    let span = tracing::info_span!(
+        "db_query",
+        otel.name = summary,
+        otel.kind = "client",
+        otel.status_code = Empty,
+        db.system.name = "sqlite",
+        db.operation.name = query.operation,
+        db.collection.name = query.collection,
+        db.query.summary = summary,
+        error.type = Empty,
+    );
+    let metrics = DatabaseMetrics::get();
+
+    async {
+        if let Some(metrics) = metrics {
+            metrics.pool_size.record(u64::from(db_pool.size()), &[]);
+            metrics
+                .pool_idle_connections
+                .record(db_pool.num_idle() as u64, &[]);
+        }
+
+        let acquire_start = Instant::now();
+        let mut connection = db_pool.acquire().await?;
+        if let Some(metrics) = metrics {
+            metrics
+                .pool_acquire_wait
+                .record(acquire_start.elapsed().as_secs_f64(), &[]);
+        }
+
+        let start = Instant::now();
+        let result = run(&mut connection).await;
+        let latency = start.elapsed().as_secs_f64();
+
+        if let Some(metrics) = metrics {
+            metrics.query_duration.record(
+                latency,
+                &[
+                    KeyValue::new("query", query.name),
+                    KeyValue::new("operation", query.operation),
+                ],
+            );
+        }
+
+        result
+    }
+    .instrument(span.clone())
+    .await
+    .inspect_err(|error| {
+        let error_type = error_type(error);
+        span.record("otel.status_code", "ERROR");
+        span.record("error.type", error_type.as_str());
+        if let Some(metrics) = metrics {
+            metrics.query_errors.add(
+                1,
+                &[
+                    KeyValue::new("query", query.name),
+                    KeyValue::new("error_type", error_type),
+                ],
+            );
+        }
+    })
+}
+
+/// `ids` as a JSON array, for queries matching any of several ids.  `SQLite` has no array
+/// parameters, so queries bind the array as text and read it with `json_each`.
+pub(crate) fn json_id_array(ids: &[i64]) -> String {
+    format!(
+        "[{}]",
+        ids.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// Short, low cardinality, description of `error`, used as the `error.type` span attribute and
+/// metric label.  Database errors are identified by their `SQLite` result code, so, for example,
+/// lock contention shows up as `5` (`SQLITE_BUSY`).
+fn error_type(error: &sqlx::Error) -> String {
+    match error {
+        sqlx::Error::Database(error) => error
+            .code()
+            .map_or_else(|| String::from("database"), std::borrow::Cow::into_owned),
+        sqlx::Error::RowNotFound => String::from("row_not_found"),
+        sqlx::Error::PoolTimedOut => String::from("pool_timed_out"),
+        sqlx::Error::PoolClosed => String::from("pool_closed"),
+        sqlx::Error::Io(_) => String::from("io"),
+        sqlx::Error::ColumnDecode { .. } | sqlx::Error::Decode(_) => String::from("decode"),
+        _ => String::from("other"),
+    }
+}
+
 /// Returns vector of ``SQLite`` database tables.  Created for use in snapshot unit testing.
 /// Created outside of test module, so `sqlx prepare` includes this query.
 #[allow(dead_code)]
@@ -63,7 +203,7 @@ mod tests {
     use assert_fs::fixture::PathChild;
     use sqlx::SqlitePool;
 
-    use crate::database::{create, get_tables, run_migrations};
+    use crate::database::{create, error_type, get_tables, json_id_array, run_migrations};
 
     #[tokio::test]
     async fn create_does_not_panic_if_database_already_exists() {
@@ -113,4 +253,46 @@ mod tests {
         let outcome = get_tables(&db_pool).await;
         insta::assert_snapshot!(format!("{outcome:?}"));
     }
+
+    #[test]
+    fn json_id_array_formats_ids_as_json_array() {
+        // act
+        let empty_outcome = json_id_array(&[]);
+        let outcome = json_id_array(&[3, 1, 2]);
+
+        // assert
+        assert_eq!(empty_outcome, "[]");
+        assert_eq!(outcome, "[3,1,2]");
+    }
+
+    #[test]
+    fn error_type_describes_error_variant() {
+        // arrange
+        let row_not_found = sqlx::Error::RowNotFound;
+        let pool_timed_out = sqlx::Error::PoolTimedOut;
+
+        // act
+        let row_not_found_outcome = error_type(&row_not_found);
+        let pool_timed_out_outcome = error_type(&pool_timed_out);
+
+        // assert
+        assert_eq!(row_not_found_outcome, "row_not_found");
+        assert_eq!(pool_timed_out_outcome, "pool_timed_out");
+    }
+
+    #[tokio::test]
+    async fn error_type_uses_sqlite_result_code_for_database_errors() {
+        // arrange
+        let db_pool = SqlitePool::connect("sqlite://:memory:").await.unwrap();
+        let error = sqlx::query("SELECT * FROM \"Missing\"")
+            .execute(&db_pool)
+            .await
+            .unwrap_err();
+
+        // act
+        let outcome = error_type(&error);
+
+        // assert
+        assert_eq!(outcome, "1");
+    }
 }
@@ -1,8 +1,10 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+pub mod auth;
 pub mod database;
 pub mod model;
 pub mod observability;
 pub mod router;
 pub mod routes;
 pub mod startup;
+pub mod workers;
@@ -2,7 +2,11 @@
 
 use std::env;
 
-use axum_graphql::{database, observability::initialise_observability, startup::Application};
+use axum_graphql::{
+    database,
+    observability::initialise_observability,
+    startup::{Application, ApplicationConfig},
+};
 use dotenvy::dotenv;
 
 #[tokio::main]
@@ -14,7 +18,9 @@ async fn main() -> anyhow::Result<()> {
     let database_url = env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://sqlite.db".into());
     database::create(&database_url).await;
 
-    let application = Application::build(&database_url, ("127.0.0.1", 8000)).await?;
+    let config = ApplicationConfig::from_env()?;
+
+    let application = Application::build(&database_url, config, ("127.0.0.1", 8000)).await?;
     application.run_until_stopped(otel_providers).await?;
 
     Ok(())
@@ -0,0 +1,305 @@
+use anyhow::Context as _;
+use async_graphql::{Enum, SimpleObject, Union};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::database::{DatabaseQuery, observe_query};
+
+use super::post::{MAX_PAGE_SIZE, UserInputError, published_state};
+
+/// Moderation state of a comment.  Only approved comments are shown to readers.
+#[derive(Clone, Copy, Debug, Enum, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+pub enum CommentStatus {
+    /// Awaiting moderation, which every new comment starts as
+    Pending,
+
+    /// Accepted by a moderator
+    Approved,
+
+    /// Turned down by a moderator
+    Rejected,
+}
+
+/// Reader comment on a published post
+#[derive(Clone, Debug, PartialEq, SimpleObject)]
+pub struct Comment {
+    pub id: i64,
+
+    /// `id` of the post commented on
+    pub post_id: i64,
+
+    /// Name the commenter chose to show alongside the comment
+    pub author_name: String,
+
+    pub body: String,
+    pub status: CommentStatus,
+
+    /// Time the comment was added
+    pub created_at: DateTime<Utc>,
+}
+
+/// Restricts paginated comment queries to a subset of comments
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CommentFilter {
+    /// Only include comments on the post with this `id`
+    pub post_id: Option<i64>,
+
+    /// Only include comments with this moderation status
+    pub status: Option<CommentStatus>,
+}
+
+/// Page of comments, oldest first, with a flag indicating whether more comments follow
+#[derive(Debug, PartialEq)]
+pub struct CommentPage {
+    pub comments: Vec<Comment>,
+    pub has_next_page: bool,
+}
+
+/// Response sent on valid add comment mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct AddCommentSuccessResponse {
+    /// Added comment, pending moderation
+    pub comment: Comment,
+}
+
+/// Response sent on add comment mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct AddCommentErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on add comment mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum AddCommentResponse {
+    AddCommentSuccessResponse(AddCommentSuccessResponse),
+    AddCommentErrorResponse(AddCommentErrorResponse),
+}
+
+/// Response sent on valid comment moderation mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct ModerateCommentSuccessResponse {
+    /// Comment, with its new status
+    pub comment: Comment,
+}
+
+/// Response sent on comment moderation mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct ModerateCommentErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on comment moderation mutations
+#[derive(Debug, PartialEq, Union)]
+pub enum ModerateCommentResponse {
+    ModerateCommentSuccessResponse(ModerateCommentSuccessResponse),
+    ModerateCommentErrorResponse(ModerateCommentErrorResponse),
+}
+
+/// Returns a page of up to 100 comments matching `filter`, ordered by `id`, oldest first, starting
+/// after the comment with `id` `after`
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Comments query", skip(db_pool))]
+pub async fn comments_query(
+    db_pool: &SqlitePool,
+    filter: CommentFilter,
+    after: Option<i64>,
+    first: Option<usize>,
+) -> Result<CommentPage, anyhow::Error> {
+    let CommentFilter { post_id, status } = filter;
+    let page_size = first.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE);
+    // fetch one extra row, to find out if there is another page beyond this one
+    let limit = i64::try_from(page_size + 1)?;
+    let mut comments = observe_query(
+        db_pool,
+        DatabaseQuery::new("Comments query", "SELECT", "Comment"),
+        async |connection| {
+            sqlx::query_as!(
+                Comment,
+                r#"
+SELECT
+    "id",
+    "post_id",
+    "author_name",
+    "body",
+    "status" AS "status: CommentStatus",
+    "created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "Comment"
+WHERE
+    ($1 IS NULL OR "post_id" = $1)
+    AND ($2 IS NULL OR "status" = $2)
+    AND ($3 IS NULL OR "id" > $3)
+ORDER BY
+    "id" ASC
+LIMIT
+    $4
+         "#,
+                post_id,
+                status,
+                after,
+                limit
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    let has_next_page = comments.len() > page_size;
+    comments.truncate(page_size);
+
+    Ok(CommentPage {
+        comments,
+        has_next_page,
+    })
+}
+
+/// Adds a comment with `author_name` and `body` to the published post matching `post_id`.  New
+/// comments are pending moderation.
+/// Returns `AddCommentResponse` with error, if the query yields no post matching `post_id`, or the
+/// post is a draft
+/// Successful addition returns an `AddCommentResponse` with the added comment
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Add comment mutation", skip(db_pool, body))]
+pub async fn add_comment_mutation(
+    db_pool: &SqlitePool,
+    post_id: i64,
+    author_name: &str,
+    body: &str,
+) -> Result<AddCommentResponse, anyhow::Error> {
+    let message = match published_state(db_pool, post_id).await? {
+        Some(true) => None,
+        Some(false) => Some(format!("Post with id `{post_id}` is not published")),
+        None => Some(format!("Did not find published post with id `{post_id}`")),
+    };
+    if let Some(message) = message {
+        return Ok(AddCommentResponse::AddCommentErrorResponse(
+            AddCommentErrorResponse {
+                error: UserInputError {
+                    field: "postId".to_string(),
+                    message,
+                    received: post_id.to_string(),
+                },
+            },
+        ));
+    }
+
+    let inserted_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Add comment mutation", "INSERT", "Comment"),
+        async |connection| {
+            sqlx::query_as!(
+                Comment,
+                r#"
+INSERT INTO
+    "Comment" ("post_id", "author_name", "body")
+VALUES
+    ($1, $2, $3)
+RETURNING
+    "id",
+    "post_id",
+    "author_name",
+    "body",
+    "status" AS "status: CommentStatus",
+    "created_at" AS "created_at: DateTime<Utc>"
+"#,
+                post_id,
+                author_name,
+                body
+            )
+            .fetch_one(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run add comment mutation")?;
+
+    Ok(AddCommentResponse::AddCommentSuccessResponse(
+        AddCommentSuccessResponse {
+            comment: inserted_row,
+        },
+    ))
+}
+
+/// Sets the moderation `status` of the comment matching `id`
+/// Returns `ModerateCommentResponse` with error, if the query yields no comment matching `id`
+/// Successful moderation returns a `ModerateCommentResponse` with the updated comment
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Moderate comment mutation", skip(db_pool))]
+pub async fn moderate_comment_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+    status: CommentStatus,
+) -> Result<ModerateCommentResponse, anyhow::Error> {
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Moderate comment mutation", "UPDATE", "Comment"),
+        async |connection| {
+            sqlx::query_as!(
+                Comment,
+                r#"
+UPDATE
+    "Comment"
+SET
+    "status" = $2
+WHERE
+    "id" = $1
+RETURNING
+    "id",
+    "post_id",
+    "author_name",
+    "body",
+    "status" AS "status: CommentStatus",
+    "created_at" AS "created_at: DateTime<Utc>"
+     "#,
+                id,
+                status
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    match updated_row {
+        Some(value) => Ok(ModerateCommentResponse::ModerateCommentSuccessResponse(
+            ModerateCommentSuccessResponse { comment: value },
+        )),
+        None => Ok(ModerateCommentResponse::ModerateCommentErrorResponse(
+            ModerateCommentErrorResponse {
+                error: UserInputError {
+                    field: "id".to_string(),
+                    message: format!("Did not find comment with id `{id}`"),
+                    received: id.to_string(),
+                },
+            },
+        )),
+    }
+}
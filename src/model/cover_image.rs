@@ -0,0 +1,443 @@
+use std::{collections::HashMap, fmt, path::PathBuf, sync::Arc};
+
+use anyhow::Context as _;
+use async_graphql::{SimpleObject, Union, UploadValue, dataloader::Loader, http::MultipartOptions};
+use imagesize::ImageType;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::io::AsyncReadExt;
+
+use crate::database::{DatabaseQuery, json_id_array, observe_query};
+
+use super::post::{Post, UserInputError, post_query};
+
+/// URL path stored cover images are served from
+pub const COVER_IMAGE_PATH: &str = "/uploads";
+
+/// Default largest cover image accepted, in bytes
+pub const DEFAULT_MAX_COVER_IMAGE_BYTES: u64 = 5 * 1_024 * 1_024;
+
+/// Image shown at the top of a post
+#[derive(Clone, Debug, PartialEq, SimpleObject)]
+pub struct CoverImage {
+    /// Path the image is served from, relative to the API origin
+    pub url: String,
+
+    /// Image width, in pixels
+    pub width: i64,
+
+    /// Image height, in pixels
+    pub height: i64,
+}
+
+/// Stored cover image for the post with `post_id`
+struct CoverImageRow {
+    post_id: i64,
+    filename: String,
+    width: i64,
+    height: i64,
+}
+
+impl From<CoverImageRow> for CoverImage {
+    fn from(row: CoverImageRow) -> Self {
+        Self {
+            url: format!("{COVER_IMAGE_PATH}/{}", row.filename),
+            width: row.width,
+            height: row.height,
+        }
+    }
+}
+
+/// Local directory cover images are written to, and the largest file accepted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoverImageStorage {
+    /// Directory holding uploaded files, named by the SHA-256 hash of their content
+    pub directory: PathBuf,
+
+    /// Largest file accepted, in bytes
+    pub max_bytes: u64,
+}
+
+impl Default for CoverImageStorage {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("uploads"),
+            max_bytes: DEFAULT_MAX_COVER_IMAGE_BYTES,
+        }
+    }
+}
+
+impl CoverImageStorage {
+    /// Read the upload directory from `UPLOADS_DIR` and size limit from `UPLOADS_MAX_BYTES`,
+    /// falling back to defaults for any which are not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `UPLOADS_MAX_BYTES` exists, but is not a non-negative integer.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            directory: std::env::var("UPLOADS_DIR").map_or(default.directory, PathBuf::from),
+            max_bytes: std::env::var("UPLOADS_MAX_BYTES").map_or(default.max_bytes, |value| {
+                value
+                    .parse()
+                    .expect("`UPLOADS_MAX_BYTES` env variable should be an integer")
+            }),
+        }
+    }
+
+    /// Bounds for multipart requests, which hold a single file, so requests larger than
+    /// `max_bytes` in total are rejected while the request body is read, before they fill the disk
+    #[must_use]
+    pub fn multipart_options(&self) -> MultipartOptions {
+        MultipartOptions::default()
+            .max_file_size(usize::try_from(self.max_bytes).unwrap_or(usize::MAX))
+            .max_num_files(1)
+    }
+}
+
+/// Reasons an uploaded file is rejected as a cover image
+#[derive(Debug, PartialEq, Eq)]
+pub enum CoverImageError {
+    /// File is larger than [`CoverImageStorage::max_bytes`]
+    TooLarge { size: u64, limit: u64 },
+
+    /// File content is not a GIF, JPEG, PNG or WebP image
+    UnsupportedType,
+
+    /// Content type declared by the client does not match the file content
+    ContentTypeMismatch {
+        declared: String,
+        detected: &'static str,
+    },
+}
+
+impl fmt::Display for CoverImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { size, limit } => {
+                write!(
+                    f,
+                    "File is {size} bytes, which exceeds the limit of {limit}"
+                )
+            }
+            Self::UnsupportedType => f.write_str("File should be a GIF, JPEG, PNG or WebP image"),
+            Self::ContentTypeMismatch { declared, detected } => {
+                write!(
+                    f,
+                    "File declared as `{declared}`, but content is `{detected}`"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CoverImageError {}
+
+/// Detected format and dimensions of an uploaded image, along with its content-hash filename
+#[derive(Debug, PartialEq, Eq)]
+pub struct InspectedImage {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// Check `content` is a supported image type, matching the `declared_content_type` sent by the
+/// client, if any, and read its dimensions.
+///
+/// # Errors
+///
+/// Errors if the content is not a supported image, or does not match the declared type.
+pub fn inspect_image(
+    content: &[u8],
+    declared_content_type: Option<&str>,
+) -> Result<InspectedImage, CoverImageError> {
+    let (content_type, extension) = match imagesize::image_type(content) {
+        Ok(ImageType::Gif) => ("image/gif", "gif"),
+        Ok(ImageType::Jpeg) => ("image/jpeg", "jpg"),
+        Ok(ImageType::Png) => ("image/png", "png"),
+        Ok(ImageType::Webp) => ("image/webp", "webp"),
+        _ => return Err(CoverImageError::UnsupportedType),
+    };
+    // clients which cannot tell the type of a file send it as `application/octet-stream`
+    if let Some(declared) = declared_content_type.filter(|value| {
+        !value.eq_ignore_ascii_case(content_type) && *value != "application/octet-stream"
+    }) {
+        return Err(CoverImageError::ContentTypeMismatch {
+            declared: declared.to_string(),
+            detected: content_type,
+        });
+    }
+    let size = imagesize::blob_size(content).map_err(|_| CoverImageError::UnsupportedType)?;
+    let (Ok(width), Ok(height)) = (i64::try_from(size.width), i64::try_from(size.height)) else {
+        return Err(CoverImageError::UnsupportedType);
+    };
+
+    Ok(InspectedImage {
+        filename: format!("{:x}.{extension}", Sha256::digest(content)),
+        content_type,
+        width,
+        height,
+    })
+}
+
+/// Response sent on valid upload cover image mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UploadCoverImageSuccessResponse {
+    /// Post, with its new cover image
+    pub post: Post,
+}
+
+/// Response sent on upload cover image mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UploadCoverImageErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on upload cover image mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum UploadCoverImageResponse {
+    UploadCoverImageSuccessResponse(UploadCoverImageSuccessResponse),
+    UploadCoverImageErrorResponse(UploadCoverImageErrorResponse),
+}
+
+/// Batches cover image lookups by post `id`
+pub struct CoverImageLoader {
+    db_pool: SqlitePool,
+}
+
+impl CoverImageLoader {
+    #[must_use]
+    pub fn new(db_pool: SqlitePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl Loader<i64> for CoverImageLoader {
+    type Value = CoverImage;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let rows = cover_images_query(&self.db_pool, keys)
+            .await
+            .map_err(Arc::new)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.post_id, CoverImage::from(row)))
+            .collect())
+    }
+}
+
+/// Returns cover images for posts matching any of `post_ids`, in no particular order
+async fn cover_images_query(
+    db_pool: &SqlitePool,
+    post_ids: &[i64],
+) -> Result<Vec<CoverImageRow>, anyhow::Error> {
+    let post_ids = json_id_array(post_ids);
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Cover images query", "SELECT", "PostCoverImage"),
+        async |connection| {
+            sqlx::query_as!(
+                CoverImageRow,
+                r#"
+SELECT
+    "post_id",
+    "filename",
+    "width",
+    "height"
+FROM
+    "PostCoverImage"
+WHERE
+    "post_id" IN (
+        SELECT
+            "value"
+        FROM
+            json_each($1)
+    )
+         "#,
+                post_ids
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    Ok(rows)
+}
+
+/// Stores `upload` in `storage`, and sets it as the cover image of the post matching `post_id`,
+/// replacing any existing cover image
+/// Returns `UploadCoverImageResponse` with error, if there is no post matching `post_id`, or the
+/// file is too large, or not a supported image
+/// Successful upload returns an `UploadCoverImageResponse` with the post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to read the upload, or write it to `storage`;
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Upload cover image mutation", skip(db_pool, upload))]
+pub async fn upload_cover_image_mutation(
+    db_pool: &SqlitePool,
+    storage: &CoverImageStorage,
+    post_id: i64,
+    upload: UploadValue,
+) -> Result<UploadCoverImageResponse, anyhow::Error> {
+    let Some(post) = post_query(db_pool, post_id).await? else {
+        return Ok(upload_cover_image_error(
+            "postId",
+            format!("Did not find post with id `{post_id}`"),
+            post_id.to_string(),
+        ));
+    };
+
+    let UploadValue {
+        filename,
+        content_type,
+        content,
+    } = upload;
+    let size = content.metadata().context("read upload metadata")?.len();
+    if size > storage.max_bytes {
+        let error = CoverImageError::TooLarge {
+            size,
+            limit: storage.max_bytes,
+        };
+        return Ok(upload_cover_image_error(
+            "file",
+            error.to_string(),
+            filename,
+        ));
+    }
+    let mut bytes = Vec::new();
+    tokio::fs::File::from_std(content)
+        .read_to_end(&mut bytes)
+        .await
+        .context("read upload content")?;
+    let image = match inspect_image(&bytes, content_type.as_deref()) {
+        Ok(value) => value,
+        Err(error) => {
+            return Ok(upload_cover_image_error(
+                "file",
+                error.to_string(),
+                filename,
+            ));
+        }
+    };
+
+    tokio::fs::create_dir_all(&storage.directory)
+        .await
+        .with_context(|| format!("create upload directory `{}`", storage.directory.display()))?;
+    tokio::fs::write(storage.directory.join(&image.filename), &bytes)
+        .await
+        .context("write cover image")?;
+
+    observe_query(
+        db_pool,
+        DatabaseQuery::new("Upload cover image mutation", "INSERT", "PostCoverImage"),
+        async |connection| {
+            sqlx::query!(
+                r#"
+INSERT INTO
+    "PostCoverImage" ("post_id", "filename", "content_type", "width", "height")
+VALUES
+    ($1, $2, $3, $4, $5)
+ON CONFLICT ("post_id") DO UPDATE
+SET
+    "filename" = excluded."filename",
+    "content_type" = excluded."content_type",
+    "width" = excluded."width",
+    "height" = excluded."height",
+    "created_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+     "#,
+                post_id,
+                image.filename,
+                image.content_type,
+                image.width,
+                image.height
+            )
+            .execute(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run upload cover image mutation")?;
+
+    Ok(UploadCoverImageResponse::UploadCoverImageSuccessResponse(
+        UploadCoverImageSuccessResponse { post },
+    ))
+}
+
+fn upload_cover_image_error(
+    field: &str,
+    message: String,
+    received: String,
+) -> UploadCoverImageResponse {
+    UploadCoverImageResponse::UploadCoverImageErrorResponse(UploadCoverImageErrorResponse {
+        error: UserInputError {
+            field: field.to_string(),
+            message,
+            received,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::{Digest, Sha256};
+
+    use super::{CoverImageError, inspect_image};
+
+    /// Signature and `IHDR` chunk of a 3 × 2 PNG, which is enough to detect its type and size
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02\x08\x06\0\0\0";
+
+    #[test]
+    fn inspect_image_reads_type_and_dimensions_and_names_file_by_hash() {
+        // arrange
+        let content = PNG_HEADER;
+
+        // act
+        let outcome = inspect_image(content, Some("image/png")).unwrap();
+
+        // assert
+        assert_eq!(outcome.content_type, "image/png");
+        assert_eq!((outcome.width, outcome.height), (3, 2));
+        assert_eq!(
+            outcome.filename,
+            format!("{:x}.png", Sha256::digest(content))
+        );
+    }
+
+    #[test]
+    fn inspect_image_rejects_unsupported_and_mismatched_content() {
+        // arrange
+        let text = b"plain text, not an image";
+
+        // act
+        let unsupported = inspect_image(text, Some("image/png"));
+        let mismatched = inspect_image(PNG_HEADER, Some("image/jpeg"));
+
+        // assert
+        assert_eq!(unsupported, Err(CoverImageError::UnsupportedType));
+        assert_eq!(
+            mismatched,
+            Err(CoverImageError::ContentTypeMismatch {
+                declared: String::from("image/jpeg"),
+                detected: "image/png",
+            })
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+use super::post::Post;
+
+/// Number of events buffered for each subscriber before the slowest subscribers start missing
+/// events
+const CHANNEL_CAPACITY: usize = 128;
+
+/// Change to a post, emitted by mutations and consumed by subscriptions
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostEvent {
+    Created(Post),
+    Published(Post),
+    DraftDeleted(Post),
+}
+
+/// Fan-out channel connecting mutations to any open GraphQL subscriptions
+#[derive(Clone)]
+pub struct PostEventBroker {
+    sender: broadcast::Sender<PostEvent>,
+}
+
+impl Default for PostEventBroker {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self { sender }
+    }
+}
+
+impl PostEventBroker {
+    /// Send `event` to all current subscribers.  Events sent while there are no subscribers are
+    /// dropped.
+    pub fn publish(&self, event: PostEvent) {
+        if self.sender.send(event).is_err() {
+            tracing::debug!("No subscribers listening for post events");
+        }
+    }
+
+    /// Returns a stream of events, starting with the next event published
+    pub fn subscribe(&self) -> impl Stream<Item = PostEvent> + use<> {
+        BroadcastStream::new(self.sender.subscribe()).filter_map(|event| match event {
+            Ok(value) => Some(value),
+            Err(error) => {
+                tracing::warn!("Post event subscriber lagging: {error}");
+                None
+            }
+        })
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use async_graphql::{
+    ErrorExtensionValues, ServerError, ServerResult, ValidationResult, Variables,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery, NextValidation},
+    parser::types::{ExecutableDocument, Selection, SelectionSet},
+};
+
+/// Upper bounds on the shape of GraphQL operations accepted by the API.  Introspection operations
+/// have separate, larger, limits, so GraphQL Playground and other tooling can load the full
+/// schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Deepest allowed nesting of selection sets
+    pub max_depth: usize,
+
+    /// Highest allowed complexity score, where each field scores 1 by default
+    pub max_complexity: usize,
+
+    /// Most aliased fields allowed in a single document
+    pub max_aliases: usize,
+
+    /// Deepest allowed nesting of selection sets in introspection operations
+    pub max_introspection_depth: usize,
+
+    /// Highest allowed complexity score of introspection operations
+    pub max_introspection_complexity: usize,
+
+    /// Most aliased fields allowed in an introspection document
+    pub max_introspection_aliases: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 12,
+            max_complexity: 256,
+            max_aliases: 32,
+            // the introspection query GraphQL Playground sends nests 15 deep, and scores 217
+            max_introspection_depth: 20,
+            max_introspection_complexity: 512,
+            max_introspection_aliases: 64,
+        }
+    }
+}
+
+impl QueryLimits {
+    /// Read limits from `GRAPHQL_MAX_DEPTH`, `GRAPHQL_MAX_COMPLEXITY` and `GRAPHQL_MAX_ALIASES`,
+    /// and introspection limits from `GRAPHQL_MAX_INTROSPECTION_DEPTH`,
+    /// `GRAPHQL_MAX_INTROSPECTION_COMPLEXITY` and `GRAPHQL_MAX_INTROSPECTION_ALIASES`, falling back
+    /// to defaults for any which are not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the environment variables exists, but is not a non-negative integer.
+    #[must_use]
+    pub fn from_env() -> Self {
+        fn read(name: &str, default: usize) -> usize {
+            env::var(name).map_or(default, |value| {
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("`{name}` env variable should be an integer"))
+            })
+        }
+
+        let default = Self::default();
+
+        Self {
+            max_depth: read("GRAPHQL_MAX_DEPTH", default.max_depth),
+            max_complexity: read("GRAPHQL_MAX_COMPLEXITY", default.max_complexity),
+            max_aliases: read("GRAPHQL_MAX_ALIASES", default.max_aliases),
+            max_introspection_depth: read(
+                "GRAPHQL_MAX_INTROSPECTION_DEPTH",
+                default.max_introspection_depth,
+            ),
+            max_introspection_complexity: read(
+                "GRAPHQL_MAX_INTROSPECTION_COMPLEXITY",
+                default.max_introspection_complexity,
+            ),
+            max_introspection_aliases: read(
+                "GRAPHQL_MAX_INTROSPECTION_ALIASES",
+                default.max_introspection_aliases,
+            ),
+        }
+    }
+}
+
+impl ExtensionFactory for QueryLimits {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(QueryLimitsExtension {
+            limits: *self,
+            introspection: AtomicBool::new(false),
+        })
+    }
+}
+
+/// Per-request state for [`QueryLimits`], remembering whether the parsed document is an
+/// introspection operation, so validation applies the matching limits
+struct QueryLimitsExtension {
+    limits: QueryLimits,
+    introspection: AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl Extension for QueryLimitsExtension {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        let introspection = is_introspection(&document);
+        self.introspection.store(introspection, Ordering::Relaxed);
+        let max_aliases = if introspection {
+            self.limits.max_introspection_aliases
+        } else {
+            self.limits.max_aliases
+        };
+
+        let aliases = count_aliases(&document);
+        if aliases > max_aliases {
+            return Err(limit_error(
+                "Query has too many aliases",
+                "TOO_MANY_ALIASES",
+                aliases,
+                max_aliases,
+            ));
+        }
+
+        Ok(document)
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let result = next.run(ctx).await?;
+        let (max_depth, max_complexity) = if self.introspection.load(Ordering::Relaxed) {
+            (
+                self.limits.max_introspection_depth,
+                self.limits.max_introspection_complexity,
+            )
+        } else {
+            (self.limits.max_depth, self.limits.max_complexity)
+        };
+
+        let mut errors = Vec::new();
+        if result.depth > max_depth {
+            errors.push(limit_error(
+                "Query is nested too deep",
+                "QUERY_TOO_DEEP",
+                result.depth,
+                max_depth,
+            ));
+        }
+        if result.complexity > max_complexity {
+            errors.push(limit_error(
+                "Query is too complex",
+                "QUERY_TOO_COMPLEX",
+                result.complexity,
+                max_complexity,
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(result)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Error for an operation exceeding a limit, reporting the computed `cost` and the `limit` in the
+/// error extensions, so clients can see how far over the limit they are
+fn limit_error(message: &str, code: &str, cost: usize, limit: usize) -> ServerError {
+    let mut extensions = ErrorExtensionValues::default();
+    extensions.set("code", code);
+    extensions.set("cost", cost);
+    extensions.set("limit", limit);
+
+    let mut error = ServerError::new(format!("{message}: {cost} exceeds limit of {limit}"), None);
+    error.extensions = Some(extensions);
+
+    error
+}
+
+/// Returns `true` when every operation in `document` only selects introspection fields
+/// (`__schema`, `__type` or `__typename`) at the top level
+fn is_introspection(document: &ExecutableDocument) -> bool {
+    document.operations.iter().all(|(_, operation)| {
+        operation
+            .node
+            .selection_set
+            .node
+            .items
+            .iter()
+            .all(|selection| match &selection.node {
+                Selection::Field(field) => field.node.name.node.starts_with("__"),
+                Selection::FragmentSpread(_) | Selection::InlineFragment(_) => false,
+            })
+    })
+}
+
+/// Total number of aliased fields the operations of `document` run.  Fragments are counted at
+/// every spread, so a fragment spread under several fields counts once for each.  Fragments
+/// spreading themselves, directly or indirectly, add nothing further.
+fn count_aliases(document: &ExecutableDocument) -> usize {
+    /// Aliases in `selection_set`, where `counted` holds totals for fragments already counted,
+    /// and `in_progress` the fragments being counted, to stop cycles
+    fn count<'a>(
+        document: &'a ExecutableDocument,
+        selection_set: &'a SelectionSet,
+        counted: &mut HashMap<&'a str, usize>,
+        in_progress: &mut HashSet<&'a str>,
+    ) -> usize {
+        selection_set
+            .items
+            .iter()
+            .map(|selection| match &selection.node {
+                Selection::Field(field) => {
+                    usize::from(field.node.alias.is_some()).saturating_add(count(
+                        document,
+                        &field.node.selection_set.node,
+                        counted,
+                        in_progress,
+                    ))
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.node.fragment_name.node.as_str();
+                    if let Some(total) = counted.get(name) {
+                        return *total;
+                    }
+                    let Some(fragment) = document.fragments.get(&spread.node.fragment_name.node)
+                    else {
+                        return 0;
+                    };
+                    if !in_progress.insert(name) {
+                        return 0;
+                    }
+                    let total = count(
+                        document,
+                        &fragment.node.selection_set.node,
+                        counted,
+                        in_progress,
+                    );
+                    in_progress.remove(name);
+                    counted.insert(name, total);
+
+                    total
+                }
+                Selection::InlineFragment(fragment) => count(
+                    document,
+                    &fragment.node.selection_set.node,
+                    counted,
+                    in_progress,
+                ),
+            })
+            .fold(0, usize::saturating_add)
+    }
+
+    let mut counted = HashMap::new();
+    let mut in_progress = HashSet::new();
+
+    document
+        .operations
+        .iter()
+        .map(|(_, operation)| {
+            count(
+                document,
+                &operation.node.selection_set.node,
+                &mut counted,
+                &mut in_progress,
+            )
+        })
+        .fold(0, usize::saturating_add)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::parser::parse_query;
+
+    use super::{count_aliases, is_introspection};
+
+    #[test]
+    fn count_aliases_counts_aliases_in_operations_and_fragments() {
+        // arrange
+        let document = parse_query(
+            "query { a: hello b: hello ...F ... on QueryRoot { c: hello } }
+             fragment F on QueryRoot { d: hello hello }",
+        )
+        .unwrap();
+
+        // act
+        let outcome = count_aliases(&document);
+
+        // assert
+        assert_eq!(outcome, 4);
+    }
+
+    #[test]
+    fn count_aliases_counts_fragments_at_each_spread() {
+        // arrange
+        let repeated = parse_query(
+            "query { x: post(id: 1) { ...F } y: post(id: 2) { ...F } }
+             fragment F on Post { a: id b: title }",
+        )
+        .unwrap();
+        let cyclic = parse_query(
+            "query { ...A }
+             fragment A on QueryRoot { a: hello ...B }
+             fragment B on QueryRoot { b: hello ...A }",
+        )
+        .unwrap();
+
+        // act
+        let repeated_outcome = count_aliases(&repeated);
+        let cyclic_outcome = count_aliases(&cyclic);
+
+        // assert
+        assert_eq!(repeated_outcome, 6);
+        assert_eq!(cyclic_outcome, 2);
+    }
+
+    #[test]
+    fn is_introspection_only_matches_introspection_fields() {
+        // arrange
+        let introspection = parse_query("query { __schema { types { name } } }").unwrap();
+        let mixed = parse_query("query { __typename hello }").unwrap();
+
+        // act
+        let introspection_outcome = is_introspection(&introspection);
+        let mixed_outcome = is_introspection(&mixed);
+
+        // assert
+        assert!(introspection_outcome);
+        assert!(!mixed_outcome);
+    }
+}
@@ -1,21 +1,93 @@
+pub mod comment;
+pub mod cover_image;
+pub mod events;
+pub mod limits;
 pub mod post;
+pub mod revision;
+pub mod schedule;
+pub mod search;
+pub mod tag;
+pub mod user;
 
-use async_graphql::{Context, EmptySubscription, Object, Schema};
+use async_graphql::{
+    Context, ErrorExtensions, Guard, ID, Object, Schema, Subscription, Upload,
+    connection::{Connection, Edge, OpaqueCursor, query},
+    dataloader::DataLoader,
+};
+use chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
+use tokio_stream::{Stream, StreamExt};
 
+use crate::{
+    auth::{AuthError, Role, Viewer},
+    observability::graphql::GraphQLObservability,
+};
+use comment::{
+    AddCommentResponse, Comment, CommentFilter, CommentPage, CommentStatus,
+    ModerateCommentResponse, add_comment_mutation, comments_query, moderate_comment_mutation,
+};
+use cover_image::{
+    CoverImageLoader, CoverImageStorage, UploadCoverImageResponse, upload_cover_image_mutation,
+};
+use events::{PostEvent, PostEventBroker};
+use limits::QueryLimits;
 use post::{
-    DeleteDraftResponse, Post, PublishResponse, ValidationError, create_draft_mutation,
-    delete_draft_mutation, drafts_query, posts_query, publish_mutation,
+    DeleteDraftResponse, Post, PostCursor, PostFilter, PostPage, PostPageRequest, PublishResponse,
+    RestoreDraftResponse, UnpublishResponse, UpdateDraftResponse, ValidationError,
+    create_draft_mutation, delete_draft_mutation, drafts_query, post_by_slug_query, post_query,
+    posts_query, publish_mutation, restore_draft_mutation, slug_words, trash_query,
+    unpublish_mutation, update_draft_mutation,
 };
+use revision::{RestoreRevisionResponse, restore_revision_mutation};
+use schedule::{
+    CancelScheduledPublishResponse, SchedulePublishResponse, cancel_scheduled_publish_mutation,
+    schedule_publish_mutation,
+};
+use search::{PostSearchPage, PostSearchResult, search_posts_query};
+use tag::{AddTagResponse, RemoveTagResponse, TagsLoader, add_tag_mutation, remove_tag_mutation};
+use user::{User, UserLoader, create_user_mutation, user_query};
+
+/// Relay connection of posts, with opaque cursors
+pub(crate) type PostConnection = Connection<OpaqueCursor<PostCursor>, Post>;
+
+/// Relay connection of comments, with opaque cursors encoding each comment's `id`
+pub(crate) type CommentConnection = Connection<OpaqueCursor<i64>, Comment>;
 
-pub(crate) type ServiceSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+/// Relay connection of search results, with opaque cursors encoding each result's position
+pub(crate) type PostSearchConnection = Connection<OpaqueCursor<usize>, PostSearchResult>;
+
+pub(crate) type ServiceSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 /// Create and return an instance of [`ServiceSchema`], representing the entire GraphQL schema.
-pub(crate) fn get_schema(db_pool: SqlitePool) -> ServiceSchema {
-    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+/// Operations exceeding `query_limits` are rejected before execution, uploaded cover images are
+/// written to `cover_images`, and subscriptions stream events sent through `events`.  Operations
+/// are traced and recorded in metrics by [`GraphQLObservability`].
+pub(crate) fn get_schema(
+    db_pool: SqlitePool,
+    query_limits: QueryLimits,
+    cover_images: CoverImageStorage,
+    events: PostEventBroker,
+) -> ServiceSchema {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(DataLoader::new(
+            UserLoader::new(db_pool.clone()),
+            tokio::spawn,
+        ))
+        .data(DataLoader::new(
+            CoverImageLoader::new(db_pool.clone()),
+            tokio::spawn,
+        ))
+        .data(DataLoader::new(
+            TagsLoader::new(db_pool.clone()),
+            tokio::spawn,
+        ))
+        .data(cover_images)
         .data(db_pool)
-        // .limit_complexity(20) // may impact GraphQL Playground documentation
-        // .limit_depth(5) // may impact GraphQL Playground documentation
+        .data(events)
+        // applied as an extension, rather than with `limit_depth` and `limit_complexity`, so
+        // introspection (used by GraphQL Playground documentation) has its own, larger, limits
+        .extension(query_limits)
+        .extension(GraphQLObservability::default())
         // Registering ValidationError manually as it is not currently directly referenced
         .register_output_type::<ValidationError>()
         .finish()
@@ -32,18 +104,384 @@ impl QueryRoot {
         "Hello everybody!"
     }
 
-    /// Returns a list of draft posts
-    async fn drafts(&self, ctx: &Context<'_>) -> Result<Vec<Post>, anyhow::Error> {
+    /// Returns a page of draft posts, ordered by `id`.  Only available to admins.
+    #[graphql(guard = "DraftsGuard::all()")]
+    async fn drafts(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<PostConnection> {
+        post_connection_query(
+            ctx,
+            false,
+            PostFilter::default(),
+            (after, before, first, last),
+        )
+        .await
+    }
+
+    /// Returns a page of published posts, newest first.  When `tag` is set, only posts with that
+    /// tag are returned, where `tag` is normalised to a slug, as tag names are.
+    #[graphql(cache_control(max_age = 60))]
+    async fn posts(
+        &self,
+        ctx: &Context<'_>,
+        tag: Option<String>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<PostConnection> {
+        let filter = PostFilter {
+            tag: tag.as_deref().map(slug_words),
+            ..PostFilter::default()
+        };
+
+        post_connection_query(ctx, true, filter, (after, before, first, last)).await
+    }
+
+    /// Returns posts with a title or body matching every term in `query`, most relevant first.
+    /// Only published posts are searched, unless `includeDrafts` is `true`, which is only
+    /// available to admins.
+    async fn search_posts(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(name = "query", validator(min_length = 1, max_length = 256))] search_text: String,
+        #[graphql(default)] include_drafts: bool,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<PostSearchConnection> {
+        if include_drafts {
+            DraftsGuard::all().check(ctx).await?;
+        }
         let db_pool = ctx.data_unchecked::<SqlitePool>();
 
-        drafts_query(db_pool).await
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<OpaqueCursor<usize>>, _, first, _| async move {
+                let offset = after.map_or(0, |cursor| cursor.0 + 1);
+
+                search_posts_query(db_pool, &search_text, include_drafts, offset, first)
+                    .await
+                    .map(|page| post_search_connection(page, offset))
+            },
+        )
+        .await
     }
 
-    /// Returns a list of published posts
-    async fn posts(&self, ctx: &Context<'_>) -> Result<Vec<Post>, anyhow::Error> {
+    /// Returns the post with `id`, or `null` if there is no such post.  Drafts are only returned
+    /// to admins and the draft author.
+    #[graphql(cache_control(max_age = 60))]
+    async fn post(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Post>, anyhow::Error> {
         let db_pool = ctx.data_unchecked::<SqlitePool>();
 
-        posts_query(db_pool).await
+        match id.parse::<i64>() {
+            Ok(value) => Ok(visible_post(ctx, post_query(db_pool, value).await?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Returns the user with `id`, or `null` if there is no such user
+    async fn user(&self, ctx: &Context<'_>, id: ID) -> Result<Option<User>, anyhow::Error> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        match id.parse::<i64>() {
+            Ok(value) => user_query(db_pool, value).await,
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Returns the post with `slug`, or `null` if there is no such post.  Drafts are only
+    /// returned to admins and the draft author.
+    #[graphql(cache_control(max_age = 60))]
+    async fn post_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        slug: String,
+    ) -> Result<Option<Post>, anyhow::Error> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(visible_post(ctx, post_by_slug_query(db_pool, &slug).await?))
+    }
+
+    /// Returns a page of comments awaiting moderation, oldest first.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn pending_comments(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<CommentConnection> {
+        let filter = CommentFilter {
+            status: Some(CommentStatus::Pending),
+            ..CommentFilter::default()
+        };
+
+        comment_connection_query(ctx, filter, after, first).await
+    }
+
+    /// Returns deleted drafts, most recently deleted first, which are purged once the retention
+    /// period has passed.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn trash(&self, ctx: &Context<'_>) -> Result<Vec<Post>, anyhow::Error> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        trash_query(db_pool).await
+    }
+}
+
+/// Returns the viewer making the request, treating requests without one as anonymous
+fn request_viewer<'ctx>(ctx: &Context<'ctx>) -> &'ctx Viewer {
+    static ANONYMOUS: Viewer = Viewer::Anonymous;
+
+    ctx.data_opt::<Viewer>().unwrap_or(&ANONYMOUS)
+}
+
+/// Error for a viewer denied access: `UNAUTHENTICATED` for anonymous viewers, who might gain
+/// access by signing in, otherwise `FORBIDDEN`
+fn access_denied(viewer: &Viewer) -> async_graphql::Error {
+    match viewer {
+        Viewer::Anonymous => AuthError::Unauthenticated.extend(),
+        Viewer::Authenticated { .. } => AuthError::Forbidden.extend(),
+    }
+}
+
+/// Allows viewers granted `role`
+pub(crate) struct RoleGuard {
+    role: Role,
+}
+
+impl RoleGuard {
+    fn new(role: Role) -> Self {
+        Self { role }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let viewer = request_viewer(ctx);
+
+        if viewer.has_role(self.role) {
+            Ok(())
+        } else {
+            Err(access_denied(viewer))
+        }
+    }
+}
+
+/// Allows admins to see drafts, as well as the author of the drafts, when `author_id` is set
+pub(crate) struct DraftsGuard {
+    author_id: Option<i64>,
+}
+
+impl DraftsGuard {
+    /// Guard for drafts from any author
+    fn all() -> Self {
+        Self { author_id: None }
+    }
+
+    /// Guard for drafts written by user `author_id`
+    fn author(author_id: i64) -> Self {
+        Self {
+            author_id: Some(author_id),
+        }
+    }
+
+    fn allows(&self, viewer: &Viewer) -> bool {
+        viewer.has_role(Role::Admin)
+            || self
+                .author_id
+                .is_some_and(|value| viewer.user_id() == Some(value))
+    }
+}
+
+impl Guard for DraftsGuard {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        let viewer = request_viewer(ctx);
+
+        if self.allows(viewer) {
+            Ok(())
+        } else {
+            Err(access_denied(viewer))
+        }
+    }
+}
+
+/// Hide `post` from the viewer, if it is a draft they are not allowed to see
+fn visible_post(ctx: &Context<'_>, post: Option<Post>) -> Option<Post> {
+    let viewer = request_viewer(ctx);
+
+    post.filter(|value| {
+        value.published
+            || value
+                .author_id
+                .map_or_else(DraftsGuard::all, DraftsGuard::author)
+                .allows(viewer)
+    })
+}
+
+/// Raw `after`, `before`, `first` and `last` Relay pagination arguments
+type ConnectionArguments = (Option<String>, Option<String>, Option<i32>, Option<i32>);
+
+/// Shared resolver for paginated post fields, returning published posts, or drafts when
+/// `published` is `false`, restricted by `filter`
+async fn post_connection_query(
+    ctx: &Context<'_>,
+    published: bool,
+    filter: PostFilter,
+    (after, before, first, last): ConnectionArguments,
+) -> async_graphql::Result<PostConnection> {
+    let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+    query(
+        after,
+        before,
+        first,
+        last,
+        |after, before, first, last| async move {
+            let page = page_request(after, before, first, last);
+            let result = if published {
+                posts_query(db_pool, &filter, &page).await
+            } else {
+                drafts_query(db_pool, &filter, &page).await
+            };
+
+            result.map(post_connection)
+        },
+    )
+    .await
+}
+
+/// Comments the viewer may see: editors, who moderate comments, see every comment, while other
+/// viewers only see approved comments
+fn visible_comment_status(viewer: &Viewer) -> Option<CommentStatus> {
+    if viewer.has_role(Role::Editor) {
+        None
+    } else {
+        Some(CommentStatus::Approved)
+    }
+}
+
+/// Shared resolver for paginated comment fields, returning comments matching `filter`
+async fn comment_connection_query(
+    ctx: &Context<'_>,
+    filter: CommentFilter,
+    after: Option<String>,
+    first: Option<i32>,
+) -> async_graphql::Result<CommentConnection> {
+    let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+    query(
+        after,
+        None,
+        first,
+        None,
+        |after: Option<OpaqueCursor<i64>>, _, first, _| async move {
+            let after = after.map(|cursor| cursor.0);
+
+            comments_query(db_pool, filter, after, first)
+                .await
+                .map(|page| comment_connection(page, after.is_some()))
+        },
+    )
+    .await
+}
+
+/// Wrap a [`CommentPage`] in a [`CommentConnection`], using each comment's `id` as its cursor
+fn comment_connection(page: CommentPage, has_previous_page: bool) -> CommentConnection {
+    let CommentPage {
+        comments,
+        has_next_page,
+    } = page;
+    let mut connection = Connection::new(has_previous_page, has_next_page);
+    connection.edges.extend(
+        comments
+            .into_iter()
+            .map(|comment| Edge::new(OpaqueCursor(comment.id), comment)),
+    );
+
+    connection
+}
+
+/// Convert decoded Relay pagination arguments into a [`PostPageRequest`]
+fn page_request(
+    after: Option<OpaqueCursor<PostCursor>>,
+    before: Option<OpaqueCursor<PostCursor>>,
+    first: Option<usize>,
+    last: Option<usize>,
+) -> PostPageRequest {
+    PostPageRequest {
+        after: after.map(|cursor| cursor.0),
+        before: before.map(|cursor| cursor.0),
+        first,
+        last,
+    }
+}
+
+/// Wrap a [`PostPage`] in a [`PostConnection`], deriving a cursor from each post
+fn post_connection(page: PostPage) -> PostConnection {
+    let PostPage {
+        posts,
+        has_previous_page,
+        has_next_page,
+    } = page;
+    let mut connection = Connection::new(has_previous_page, has_next_page);
+    connection.edges.extend(
+        posts
+            .into_iter()
+            .map(|post| Edge::new(OpaqueCursor(PostCursor::from(&post)), post)),
+    );
+
+    connection
+}
+
+/// Wrap a [`PostSearchPage`], starting `offset` results into the full result list, in a
+/// [`PostSearchConnection`]
+fn post_search_connection(page: PostSearchPage, offset: usize) -> PostSearchConnection {
+    let PostSearchPage {
+        results,
+        has_next_page,
+    } = page;
+    let mut connection = Connection::new(offset > 0, has_next_page);
+    connection.edges.extend(
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| Edge::new(OpaqueCursor(offset + index), result)),
+    );
+
+    connection
+}
+
+/// Returns the viewer making the request, or an `UNAUTHENTICATED` error if they are anonymous
+fn authenticated_viewer<'ctx>(ctx: &Context<'ctx>) -> async_graphql::Result<&'ctx Viewer> {
+    match request_viewer(ctx) {
+        viewer @ Viewer::Authenticated { .. } => Ok(viewer),
+        Viewer::Anonymous => Err(AuthError::Unauthenticated.extend()),
+    }
+}
+
+/// Returns an error unless the viewer was granted `role`, or wrote the post with `id`.  Posts
+/// without an author may only be changed by viewers granted `role`, and missing posts are left for
+/// the mutation to report on.
+async fn check_author_or_role(ctx: &Context<'_>, id: i64, role: Role) -> async_graphql::Result<()> {
+    let viewer = authenticated_viewer(ctx)?;
+    if viewer.has_role(role) {
+        return Ok(());
+    }
+    let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+    match post_query(db_pool, id).await? {
+        Some(post) if post.author_id.is_none() || post.author_id != viewer.user_id() => {
+            Err(access_denied(viewer))
+        }
+        _ => Ok(()),
     }
 }
 
@@ -52,37 +490,273 @@ pub(crate) struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
-    /// Creates a new draft with `title` and `body`
+    /// Creates a new user with display name `name`.  Only available to admins.
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
+    async fn create_user(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_length = 1, max_length = 64))] name: String,
+    ) -> async_graphql::Result<User> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(create_user_mutation(db_pool, &name).await?)
+    }
+
+    /// Creates a new draft with `title` and `body`, written by the viewer, when their token
+    /// subject is a user id
     async fn create_draft(
         &self,
         ctx: &Context<'_>,
         #[graphql(validator(min_length = 3, max_length = 64))] title: String,
         #[graphql(validator(min_length = 3, max_length = 64_000))] body: String,
-    ) -> Result<Post, anyhow::Error> {
+    ) -> async_graphql::Result<Post> {
+        let viewer = authenticated_viewer(ctx)?;
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        let post = create_draft_mutation(db_pool, &title, &body, viewer.user_id()).await?;
+        broker.publish(PostEvent::Created(post.clone()));
+
+        Ok(post)
+    }
+
+    /// Replaces `title` and `body` of the draft post with `id`.  Only available to admins and the
+    /// draft author.
+    async fn update_draft(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+        #[graphql(validator(min_length = 3, max_length = 64))] title: String,
+        #[graphql(validator(min_length = 3, max_length = 64_000))] body: String,
+    ) -> async_graphql::Result<UpdateDraftResponse> {
+        check_author_or_role(ctx, id, Role::Admin).await?;
         let db_pool = ctx.data_unchecked::<SqlitePool>();
 
-        create_draft_mutation(db_pool, &title, &body).await
+        Ok(update_draft_mutation(db_pool, id, &title, &body).await?)
     }
 
-    /// Deletes the draft post with `id`
+    /// Moves the draft post with `id` to the trash, from where it can be restored until purged.
+    /// Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
     async fn delete_draft(
         &self,
         ctx: &Context<'_>,
         #[graphql(validator(minimum = 0))] id: i64,
-    ) -> Result<DeleteDraftResponse, anyhow::Error> {
+    ) -> async_graphql::Result<DeleteDraftResponse> {
         let db_pool = ctx.data_unchecked::<SqlitePool>();
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        let response = delete_draft_mutation(db_pool, id).await?;
+        if let DeleteDraftResponse::DeleteDraftSuccessResponse(value) = &response {
+            broker.publish(PostEvent::DraftDeleted(value.post.clone()));
+        }
 
-        delete_draft_mutation(db_pool, id).await
+        Ok(response)
     }
 
-    /// Updates `published` field for post with `id` to `true`
+    /// Moves the deleted draft post with `id` out of the trash.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn restore_draft(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+    ) -> async_graphql::Result<RestoreDraftResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(restore_draft_mutation(db_pool, id).await?)
+    }
+
+    /// Updates `published` field for post with `id` to `true`.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
     async fn publish(
         &self,
         ctx: &Context<'_>,
         #[graphql(validator(minimum = 0))] id: i64,
-    ) -> Result<PublishResponse, anyhow::Error> {
+    ) -> async_graphql::Result<PublishResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        let response = publish_mutation(db_pool, id).await?;
+        if let PublishResponse::PublishSuccessResponse(value) = &response {
+            broker.publish(PostEvent::Published(value.post.clone()));
+        }
+
+        Ok(response)
+    }
+
+    /// Schedules the draft post with `id` to be published at `at`, replacing any earlier
+    /// schedule.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn schedule_publish(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+        at: DateTime<Utc>,
+    ) -> async_graphql::Result<SchedulePublishResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(schedule_publish_mutation(db_pool, id, at).await?)
+    }
+
+    /// Cancels the scheduled publishing of the draft post with `id`.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn cancel_scheduled_publish(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+    ) -> async_graphql::Result<CancelScheduledPublishResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(cancel_scheduled_publish_mutation(db_pool, id).await?)
+    }
+
+    /// Sets `file` as the cover image of the post with `postId`, replacing any existing cover
+    /// image.  `file` is sent following the GraphQL multipart request specification, and should
+    /// be a GIF, JPEG, PNG or WebP image.  Only available to editors and the post author.
+    async fn upload_cover_image(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] post_id: i64,
+        file: Upload,
+    ) -> async_graphql::Result<UploadCoverImageResponse> {
+        check_author_or_role(ctx, post_id, Role::Editor).await?;
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+        let storage = ctx.data_unchecked::<CoverImageStorage>();
+        let upload = file.value(ctx)?;
+
+        Ok(upload_cover_image_mutation(db_pool, storage, post_id, upload).await?)
+    }
+
+    /// Adds the tag `name` to the post with `postId`, creating the tag if it does not yet exist.
+    /// `name` is normalised to a slug, so `Rust Lang` and `rust-lang` name the same tag.  Only
+    /// available to editors and the post author.
+    async fn add_tag(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] post_id: i64,
+        name: String,
+    ) -> async_graphql::Result<AddTagResponse> {
+        check_author_or_role(ctx, post_id, Role::Editor).await?;
         let db_pool = ctx.data_unchecked::<SqlitePool>();
 
-        publish_mutation(db_pool, id).await
+        Ok(add_tag_mutation(db_pool, post_id, &name).await?)
+    }
+
+    /// Removes the tag `name` from the post with `postId`.  Only available to editors and the
+    /// post author.
+    async fn remove_tag(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] post_id: i64,
+        name: String,
+    ) -> async_graphql::Result<RemoveTagResponse> {
+        check_author_or_role(ctx, post_id, Role::Editor).await?;
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(remove_tag_mutation(db_pool, post_id, &name).await?)
+    }
+
+    /// Adds a comment by `authorName` to the published post with `postId`.  The comment is only
+    /// shown to readers once approved by an editor.
+    async fn add_comment(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] post_id: i64,
+        #[graphql(validator(min_length = 1, max_length = 64))] author_name: String,
+        #[graphql(validator(min_length = 1, max_length = 4_000))] body: String,
+    ) -> async_graphql::Result<AddCommentResponse> {
+        authenticated_viewer(ctx)?;
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(add_comment_mutation(db_pool, post_id, &author_name, &body).await?)
+    }
+
+    /// Approves the comment with `id`, showing it to readers.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn approve_comment(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+    ) -> async_graphql::Result<ModerateCommentResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(moderate_comment_mutation(db_pool, id, CommentStatus::Approved).await?)
+    }
+
+    /// Rejects the comment with `id`, hiding it from readers.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn reject_comment(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+    ) -> async_graphql::Result<ModerateCommentResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(moderate_comment_mutation(db_pool, id, CommentStatus::Rejected).await?)
+    }
+
+    /// Replaces the title and body of the post with `postId` with those of its revision with
+    /// `revisionId`.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn restore_revision(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] post_id: i64,
+        #[graphql(validator(minimum = 0))] revision_id: i64,
+    ) -> async_graphql::Result<RestoreRevisionResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(restore_revision_mutation(db_pool, post_id, revision_id).await?)
+    }
+
+    /// Updates `published` field for post with `id` to `false`, returning it to draft.  Only
+    /// available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn unpublish(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(minimum = 0))] id: i64,
+    ) -> async_graphql::Result<UnpublishResponse> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(unpublish_mutation(db_pool, id).await?)
+    }
+}
+
+/// GraphQL API subscription type
+pub(crate) struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Streams each new draft post, as it is created.  Only available to admins.
+    #[graphql(guard = "DraftsGuard::all()")]
+    async fn post_created(&self, ctx: &Context<'_>) -> impl Stream<Item = Post> + use<> {
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        broker.subscribe().filter_map(|event| match event {
+            PostEvent::Created(post) => Some(post),
+            _ => None,
+        })
+    }
+
+    /// Streams each post, as it is published
+    async fn post_published(&self, ctx: &Context<'_>) -> impl Stream<Item = Post> + use<> {
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        broker.subscribe().filter_map(|event| match event {
+            PostEvent::Published(post) => Some(post),
+            _ => None,
+        })
+    }
+
+    /// Streams each draft post, as it is deleted.  Only available to admins.
+    #[graphql(guard = "DraftsGuard::all()")]
+    async fn draft_deleted(&self, ctx: &Context<'_>) -> impl Stream<Item = Post> + use<> {
+        let broker = ctx.data_unchecked::<PostEventBroker>();
+
+        broker.subscribe().filter_map(|event| match event {
+            PostEvent::DraftDeleted(post) => Some(post),
+            _ => None,
+        })
     }
 }
@@ -1,14 +1,129 @@
-use anyhow::Context;
-use async_graphql::{Interface, SimpleObject, Union};
-use serde::Deserialize;
+use anyhow::Context as _;
+use async_graphql::{
+    ComplexObject, Context, Interface, SimpleObject, Union, dataloader::DataLoader,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
-#[derive(Debug, Deserialize, PartialEq, SimpleObject)]
+use crate::{
+    auth::Role,
+    database::{DatabaseQuery, observe_query},
+};
+
+use super::{
+    CommentConnection, RoleGuard,
+    comment::CommentFilter,
+    comment_connection_query,
+    cover_image::{CoverImage, CoverImageLoader},
+    request_viewer,
+    revision::{PostRevision, post_revision_query, post_revisions_query, unified_diff},
+    tag::{Tag, TagsLoader},
+    user::{User, UserLoader},
+    visible_comment_status,
+};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, SimpleObject)]
+#[graphql(complex)]
 pub struct Post {
     pub id: i64,
     pub title: String,
+
+    /// Unique, URL-friendly identifier, generated from the title when the draft is created
+    pub slug: String,
+
     pub body: String,
     pub published: bool,
+
+    /// Time the post was first created, as a draft
+    pub created_at: DateTime<Utc>,
+
+    /// Time the post was last changed
+    pub updated_at: DateTime<Utc>,
+
+    /// Time the post was published, or `null` for drafts
+    pub published_at: Option<DateTime<Utc>>,
+
+    /// `id` of the user who wrote the post, resolved through the `author` field
+    #[graphql(skip)]
+    pub author_id: Option<i64>,
+
+    /// Time the draft was moved to the trash, or `null` for posts which are not deleted
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// Time the draft is scheduled to be published, or `null` if it is not scheduled
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+#[ComplexObject]
+impl Post {
+    /// User who wrote the post, or `null` for posts with no recorded author
+    async fn author(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+        let Some(author_id) = self.author_id else {
+            return Ok(None);
+        };
+        let loader = ctx.data_unchecked::<DataLoader<UserLoader>>();
+
+        Ok(loader.load_one(author_id).await?)
+    }
+
+    /// Image shown at the top of the post, or `null` if none was uploaded
+    async fn cover_image(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<CoverImage>> {
+        let loader = ctx.data_unchecked::<DataLoader<CoverImageLoader>>();
+
+        Ok(loader.load_one(self.id).await?)
+    }
+
+    /// Tags added to the post, ordered by name
+    async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Tag>> {
+        let loader = ctx.data_unchecked::<DataLoader<TagsLoader>>();
+
+        Ok(loader.load_one(self.id).await?.unwrap_or_default())
+    }
+
+    /// Returns a page of comments on the post, oldest first.  Comments awaiting moderation, or
+    /// rejected, are only returned to editors.
+    async fn comments(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> async_graphql::Result<CommentConnection> {
+        let filter = CommentFilter {
+            post_id: Some(self.id),
+            status: visible_comment_status(request_viewer(ctx)),
+        };
+
+        comment_connection_query(ctx, filter, after, first).await
+    }
+
+    /// Snapshots of the post content, newest first.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn revisions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<PostRevision>> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+
+        Ok(post_revisions_query(db_pool, self.id).await?)
+    }
+
+    /// Line-based unified diff of the post content between its revisions with ids `from` and
+    /// `to`, or `null` if either is not a revision of the post.  Only available to editors.
+    #[graphql(guard = "RoleGuard::new(Role::Editor)")]
+    async fn diff(
+        &self,
+        ctx: &Context<'_>,
+        from: i64,
+        to: i64,
+    ) -> async_graphql::Result<Option<String>> {
+        let db_pool = ctx.data_unchecked::<SqlitePool>();
+        let Some(from_revision) = post_revision_query(db_pool, self.id, from).await? else {
+            return Ok(None);
+        };
+        let Some(to_revision) = post_revision_query(db_pool, self.id, to).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(unified_diff(&from_revision, &to_revision)))
+    }
 }
 
 #[derive(Debug, PartialEq, SimpleObject)]
@@ -52,18 +167,38 @@ pub enum DeleteDraftResponse {
     DeleteDraftErrorResponse(DeleteDraftErrorResponse),
 }
 
+/// Response sent on valid restore draft mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RestoreDraftSuccessResponse {
+    /// Draft, moved out of the trash
+    pub post: Post,
+}
+
+/// Response sent on restore draft mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RestoreDraftErrorResponse {
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on restore draft mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum RestoreDraftResponse {
+    RestoreDraftSuccessResponse(RestoreDraftSuccessResponse),
+    RestoreDraftErrorResponse(RestoreDraftErrorResponse),
+}
+
 /// Response sent on valid publish draft mutation
 #[derive(Debug, PartialEq, SimpleObject)]
 pub struct PublishSuccessResponse {
     /// Published post
-    post: Post,
+    pub post: Post,
 }
 
 /// Response sent on publish draft mutation with validation issues identified
 #[derive(Debug, PartialEq, SimpleObject)]
 pub struct PublishErrorResponse {
     /// User input error details
-    error: UserInputError,
+    pub error: UserInputError,
 }
 
 /// Union of responses sent on publish draft mutation
@@ -73,73 +208,593 @@ pub enum PublishResponse {
     PublishErrorResponse(PublishErrorResponse),
 }
 
-/// Return a list of up to 100 draft posts
+/// Response sent on valid unpublish mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UnpublishSuccessResponse {
+    /// Post, returned to draft
+    pub post: Post,
+}
+
+/// Response sent on unpublish mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UnpublishErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on unpublish mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum UnpublishResponse {
+    UnpublishSuccessResponse(UnpublishSuccessResponse),
+    UnpublishErrorResponse(UnpublishErrorResponse),
+}
+
+/// Response sent on valid update draft mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UpdateDraftSuccessResponse {
+    /// Updated draft
+    pub post: Post,
+}
+
+/// Response sent on update draft mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct UpdateDraftErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on update draft mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum UpdateDraftResponse {
+    UpdateDraftSuccessResponse(UpdateDraftSuccessResponse),
+    UpdateDraftErrorResponse(UpdateDraftErrorResponse),
+}
+
+/// Largest number of posts returned in a single page, also used when the client requests
+/// neither `first` nor `last`
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Position of a post within a paginated list.  Drafts are ordered by `id` alone, while published
+/// posts are ordered by `published_at` (newest first), using `id` to break ties.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PostCursor {
+    pub id: i64,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+impl From<&Post> for PostCursor {
+    fn from(post: &Post) -> Self {
+        Self {
+            id: post.id,
+            published_at: post.published_at,
+        }
+    }
+}
+
+/// Restricts paginated post queries to a subset of posts
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostFilter {
+    /// Only include posts written by the user with this `id`
+    pub author_id: Option<i64>,
+
+    /// Only include posts with the tag of this name, which should already be normalised
+    pub tag: Option<String>,
+}
+
+/// Window of posts requested using Relay-style pagination arguments, with `after` and `before`
+/// cursors already decoded
+#[derive(Debug, Default, PartialEq)]
+pub struct PostPageRequest {
+    pub after: Option<PostCursor>,
+    pub before: Option<PostCursor>,
+    pub first: Option<usize>,
+    pub last: Option<usize>,
+}
+
+impl PostPageRequest {
+    /// Requests with `last` (and no `first`) page backwards from `before`, all other requests page
+    /// forwards from `after`
+    fn is_backwards(&self) -> bool {
+        self.last.is_some() && self.first.is_none()
+    }
+
+    fn page_size(&self) -> usize {
+        self.first
+            .or(self.last)
+            .unwrap_or(MAX_PAGE_SIZE)
+            .min(MAX_PAGE_SIZE)
+    }
+
+    /// Row limit for the page query.  This is one more than the page size, to find out if there is
+    /// another page beyond this one.
+    fn query_limit(&self) -> Result<i64, std::num::TryFromIntError> {
+        i64::try_from(self.page_size() + 1)
+    }
+
+    /// Trim `rows`, fetched with [`Self::query_limit`], down to the requested page
+    fn to_page(&self, mut rows: Vec<Post>) -> PostPage {
+        let page_size = self.page_size();
+        let has_more = rows.len() > page_size;
+        rows.truncate(page_size);
+
+        if self.is_backwards() {
+            rows.reverse();
+            PostPage {
+                posts: rows,
+                has_previous_page: has_more,
+                has_next_page: self.before.is_some(),
+            }
+        } else {
+            PostPage {
+                posts: rows,
+                has_previous_page: self.after.is_some(),
+                has_next_page: has_more,
+            }
+        }
+    }
+}
+
+/// Page of posts, with flags indicating whether more posts exist either side of the page
+#[derive(Debug, PartialEq)]
+pub struct PostPage {
+    pub posts: Vec<Post>,
+    pub has_previous_page: bool,
+    pub has_next_page: bool,
+}
+
+/// Return a page of up to 100 draft posts, ordered by `id`
 ///
 /// # Errors
 ///
 /// Errors if:
 ///  - unable to connect to database; or
 ///  - if SQL query fails.
+// each paging direction has its own query, so the sort order can use an index
+#[expect(clippy::too_many_lines)]
 #[tracing::instrument(name = "Drafts query", skip(db_pool))]
-pub async fn drafts_query(db_pool: &SqlitePool) -> Result<Vec<Post>, anyhow::Error> {
-    let limit = 100;
-    let rows = sqlx::query_as!(
-        Post,
-        r#"
+pub async fn drafts_query(
+    db_pool: &SqlitePool,
+    filter: &PostFilter,
+    page: &PostPageRequest,
+) -> Result<PostPage, anyhow::Error> {
+    let author_id = filter.author_id;
+    let tag = filter.tag.as_deref();
+    let after = page.after.map(|cursor| cursor.id);
+    let before = page.before.map(|cursor| cursor.id);
+    let limit = page.query_limit()?;
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Drafts query", "SELECT", "Post"),
+        async |connection| {
+            if page.is_backwards() {
+                sqlx::query_as!(
+                    Post,
+                    r#"
 SELECT
     "id",
     "title",
+    "slug",
     "body",
-    "published"
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+FROM
+    "Post"
+WHERE
+    "published" = FALSE
+    AND "deleted_at" IS NULL
+    AND ($1 IS NULL OR "id" > $1)
+    AND ($2 IS NULL OR "id" < $2)
+    AND ($4 IS NULL OR "author_id" = $4)
+    AND (
+        $5 IS NULL
+        OR "id" IN (
+            SELECT
+                "PostTag"."post_id"
+            FROM
+                "PostTag"
+                INNER JOIN "Tag" ON "Tag"."id" = "PostTag"."tag_id"
+            WHERE
+                "Tag"."name" = $5
+        )
+    )
+ORDER BY
+    "id" DESC
+LIMIT
+    $3
+         "#,
+                    after,
+                    before,
+                    limit,
+                    author_id,
+                    tag
+                )
+                .fetch_all(connection)
+                .await
+            } else {
+                sqlx::query_as!(
+                    Post,
+                    r#"
+SELECT
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
 FROM
     "Post"
 WHERE
     "published" = FALSE
+    AND "deleted_at" IS NULL
+    AND ($1 IS NULL OR "id" > $1)
+    AND ($2 IS NULL OR "id" < $2)
+    AND ($4 IS NULL OR "author_id" = $4)
+    AND (
+        $5 IS NULL
+        OR "id" IN (
+            SELECT
+                "PostTag"."post_id"
+            FROM
+                "PostTag"
+                INNER JOIN "Tag" ON "Tag"."id" = "PostTag"."tag_id"
+            WHERE
+                "Tag"."name" = $5
+        )
+    )
+ORDER BY
+    "id" ASC
 LIMIT
-    ?
+    $3
          "#,
-        limit
+                    after,
+                    before,
+                    limit,
+                    author_id,
+                    tag
+                )
+                .fetch_all(connection)
+                .await
+            }
+        },
     )
-    .fetch_all(db_pool)
     .await?;
 
-    Ok(rows)
+    Ok(page.to_page(rows))
 }
 
-/// Returns a list of up to 100 published posts
+/// Returns a page of up to 100 published posts, ordered by `published_at`, newest first
 ///
 /// # Errors
 ///
 /// Errors if:
 ///  - unable to connect to database; or
 ///  - if SQL query fails.
+// each paging direction has its own query, so the sort order can use an index
+#[expect(clippy::too_many_lines)]
 #[tracing::instrument(name = "Posts query", skip(db_pool))]
-pub async fn posts_query(db_pool: &SqlitePool) -> Result<Vec<Post>, anyhow::Error> {
-    let limit = 100;
-    let rows = sqlx::query_as!(
-        Post,
-        r#"
+pub async fn posts_query(
+    db_pool: &SqlitePool,
+    filter: &PostFilter,
+    page: &PostPageRequest,
+) -> Result<PostPage, anyhow::Error> {
+    let author_id = filter.author_id;
+    let tag = filter.tag.as_deref();
+    let after_id = page.after.map(|cursor| cursor.id);
+    let after_published_at = page.after.and_then(|cursor| cursor.published_at);
+    let before_id = page.before.map(|cursor| cursor.id);
+    let before_published_at = page.before.and_then(|cursor| cursor.published_at);
+    let limit = page.query_limit()?;
+
+    // `published_at` is stored in one format, so cursor values, normalised to it, compare correctly
+    // with the column, leaving the `published`, `published_at` index usable for ordering
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Posts query", "SELECT", "Post"),
+        async |connection| {
+            if page.is_backwards() {
+                sqlx::query_as!(
+                    Post,
+                    r#"
 SELECT
     "id",
     "title",
+    "slug",
     "body",
-    "published"
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
 FROM
     "Post"
 WHERE
     "published" = TRUE
+    AND "deleted_at" IS NULL
+    AND (
+        $1 IS NULL
+        OR "published_at" < strftime('%Y-%m-%dT%H:%M:%fZ', $2)
+        OR ("published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', $2) AND "id" < $1)
+    )
+    AND (
+        $3 IS NULL
+        OR "published_at" > strftime('%Y-%m-%dT%H:%M:%fZ', $4)
+        OR ("published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', $4) AND "id" > $3)
+    )
+    AND ($6 IS NULL OR "author_id" = $6)
+    AND (
+        $7 IS NULL
+        OR "id" IN (
+            SELECT
+                "PostTag"."post_id"
+            FROM
+                "PostTag"
+                INNER JOIN "Tag" ON "Tag"."id" = "PostTag"."tag_id"
+            WHERE
+                "Tag"."name" = $7
+        )
+    )
+ORDER BY
+    "published_at" ASC,
+    "id" ASC
 LIMIT
-    ?
+    $5
          "#,
-        limit
+                    after_id,
+                    after_published_at,
+                    before_id,
+                    before_published_at,
+                    limit,
+                    author_id,
+                    tag
+                )
+                .fetch_all(connection)
+                .await
+            } else {
+                sqlx::query_as!(
+                    Post,
+                    r#"
+SELECT
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+FROM
+    "Post"
+WHERE
+    "published" = TRUE
+    AND "deleted_at" IS NULL
+    AND (
+        $1 IS NULL
+        OR "published_at" < strftime('%Y-%m-%dT%H:%M:%fZ', $2)
+        OR ("published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', $2) AND "id" < $1)
+    )
+    AND (
+        $3 IS NULL
+        OR "published_at" > strftime('%Y-%m-%dT%H:%M:%fZ', $4)
+        OR ("published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', $4) AND "id" > $3)
+    )
+    AND ($6 IS NULL OR "author_id" = $6)
+    AND (
+        $7 IS NULL
+        OR "id" IN (
+            SELECT
+                "PostTag"."post_id"
+            FROM
+                "PostTag"
+                INNER JOIN "Tag" ON "Tag"."id" = "PostTag"."tag_id"
+            WHERE
+                "Tag"."name" = $7
+        )
+    )
+ORDER BY
+    "published_at" DESC,
+    "id" DESC
+LIMIT
+    $5
+         "#,
+                    after_id,
+                    after_published_at,
+                    before_id,
+                    before_published_at,
+                    limit,
+                    author_id,
+                    tag
+                )
+                .fetch_all(connection)
+                .await
+            }
+        },
     )
-    .fetch_all(db_pool)
     .await?;
 
-    Ok(rows)
+    Ok(page.to_page(rows))
+}
+
+/// Returns the post matching `id`, or `None` if there is no such post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Post query", skip(db_pool))]
+pub async fn post_query(db_pool: &SqlitePool, id: i64) -> Result<Option<Post>, anyhow::Error> {
+    let row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Post query", "SELECT", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+SELECT
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+FROM
+    "Post"
+WHERE
+    "id" = $1
+    AND "deleted_at" IS NULL
+         "#,
+                id
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await?;
+
+    Ok(row)
 }
 
-/// Creates a new draft with `title` and `body`
+/// Returns the post matching `slug`, or `None` if there is no such post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Post by slug query", skip(db_pool))]
+pub async fn post_by_slug_query(
+    db_pool: &SqlitePool,
+    slug: &str,
+) -> Result<Option<Post>, anyhow::Error> {
+    let row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Post by slug query", "SELECT", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+SELECT
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+FROM
+    "Post"
+WHERE
+    "slug" = $1
+    AND "deleted_at" IS NULL
+         "#,
+                slug
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await?;
+
+    Ok(row)
+}
+
+/// Keep lowercase ASCII letters and digits of `text`, replacing every other run of characters
+/// with a single hyphen.  Returns an empty string if `text` has no ASCII letters or digits.
+pub(crate) fn slug_words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Convert `title` to a URL-friendly slug, keeping lowercase ASCII letters and digits, and
+/// replacing every other run of characters with a single hyphen
+#[must_use]
+pub fn slugify(title: &str) -> String {
+    let slug = slug_words(title);
+
+    if slug.is_empty() {
+        String::from("post")
+    } else {
+        slug
+    }
+}
+
+/// Number of times [`create_draft_mutation`] chooses a slug, when concurrent mutations keep taking
+/// the one chosen
+const CREATE_DRAFT_ATTEMPTS: usize = 16;
+
+/// Returns a slug, generated from `title`, which is not yet used by any post.  When the plain slug
+/// is taken, a numeric suffix is appended, starting from `-2`.
+async fn unique_slug(db_pool: &SqlitePool, title: &str) -> Result<String, sqlx::Error> {
+    let base = slugify(title);
+
+    // slugs only contain `[a-z0-9-]`, so `base` needs no escaping within the `LIKE` pattern
+    let pattern = format!("{base}-%");
+    let existing_slugs = observe_query(
+        db_pool,
+        DatabaseQuery::new("Unique slug query", "SELECT", "Post"),
+        async |connection| {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    "slug"
+FROM
+    "Post"
+WHERE
+    "slug" = $1
+    OR "slug" LIKE $2
+     "#,
+                base,
+                pattern
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    if !existing_slugs.contains(&base) {
+        return Ok(base);
+    }
+    // at most `existing_slugs.len() - 1` suffixes are taken, so this range has a free one
+    let suffix = (2..=existing_slugs.len() + 1)
+        .find(|suffix| !existing_slugs.contains(&format!("{base}-{suffix}")))
+        .unwrap_or(existing_slugs.len() + 1);
+
+    Ok(format!("{base}-{suffix}"))
+}
+
+/// Creates a new draft with `title` and `body`, written by the user with `author_id`, and a unique
+/// slug generated from `title`
 /// Successful creation returns the created post
 ///
 /// # Errors
@@ -152,34 +807,162 @@ pub async fn create_draft_mutation(
     db_pool: &SqlitePool,
     title: &str,
     body: &str,
+    author_id: Option<i64>,
 ) -> Result<Post, anyhow::Error> {
-    let inserted_row = sqlx::query_as!(
-        Post,
-        r#"
+    // a concurrent mutation may take the slug between choosing it and inserting the draft, in
+    // which case the insert violates `Post_slug_key`, and a fresh slug is chosen
+    let mut attempt = 1;
+    loop {
+        let slug = unique_slug(db_pool, title)
+            .await
+            .context("run create draft mutation for post")?;
+        match insert_draft(db_pool, title, &slug, body, author_id).await {
+            Err(error) if attempt < CREATE_DRAFT_ATTEMPTS && is_unique_violation(&error) => {
+                tracing::info!("Slug `{slug}` was taken concurrently, so choosing another");
+                attempt += 1;
+            }
+            result => {
+                return result
+                    .inspect_err(|err| {
+                        tracing::error!("Failed to execute query: {err:?}");
+                    })
+                    .context("run create draft mutation for post");
+            }
+        }
+    }
+}
+
+/// Returns `true` if `error` is a unique constraint violation
+fn is_unique_violation(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(sqlx::error::DatabaseError::is_unique_violation)
+}
+
+/// Inserts a draft with `title`, `slug` and `body`, written by the user with `author_id`
+async fn insert_draft(
+    db_pool: &SqlitePool,
+    title: &str,
+    slug: &str,
+    body: &str,
+    author_id: Option<i64>,
+) -> Result<Post, sqlx::Error> {
+    observe_query(
+        db_pool,
+        DatabaseQuery::new("Create draft mutation", "INSERT", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
 INSERT INTO
-    "Post" ("title", "body", "published")
+    "Post" ("title", "slug", "body", "published", "author_id")
 VALUES
-    ($1, $2, false)
+    ($1, $2, $3, false, $4)
 RETURNING
     "id",
     "title",
+    "slug",
     "body",
-    "published"
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
 "#,
-        title,
-        body
+                title,
+                slug,
+                body,
+                author_id
+            )
+            .fetch_one(connection)
+            .await
+        },
+    )
+    .await
+}
+
+/// Replaces `title` and `body` of the draft matching `id`
+/// Returns `UpdateDraftResponse` with error, if the query yields no draft matching `id`, including
+/// when the post matching `id` is already published
+/// Successful update returns an `UpdateDraftResponse` with the updated draft
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Update draft mutation", skip(db_pool))]
+pub async fn update_draft_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+    title: &str,
+    body: &str,
+) -> Result<UpdateDraftResponse, anyhow::Error> {
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Update draft mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "title" = $2,
+    "body" = $3,
+    "updated_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+WHERE
+    (
+        "id" = $1
+        AND "published" = FALSE
+        AND "deleted_at" IS NULL
+    )
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                id,
+                title,
+                body
+            )
+            .fetch_optional(connection)
+            .await
+        },
     )
-    .fetch_one(db_pool)
     .await
     .inspect_err(|err| {
         tracing::error!("Failed to execute query: {err:?}");
-    })
-    .context("run create draft mutation for post")?;
+    })?;
 
-    Ok(inserted_row)
+    match updated_row {
+        Some(value) => Ok(UpdateDraftResponse::UpdateDraftSuccessResponse(
+            UpdateDraftSuccessResponse { post: value },
+        )),
+        None => Ok(UpdateDraftResponse::UpdateDraftErrorResponse(
+            UpdateDraftErrorResponse {
+                error: UserInputError {
+                    field: "id".to_string(),
+                    message: format!("Did not find draft post with id `{id}`"),
+                    received: id.to_string(),
+                },
+            },
+        )),
+    }
 }
 
-/// Deletes draft matching `id`
+/// Moves draft matching `id` to the trash, where it is kept until restored with
+/// [`restore_draft_mutation`] or purged with [`purge_deleted_drafts_mutation`]
 /// Returns `DeleteDraftResponse` with error, if the query yields no post matching `id`
 /// Successful deletion returns a `DeleteDraftResponse` with the deleted post
 ///
@@ -193,25 +976,43 @@ pub async fn delete_draft_mutation(
     db_pool: &SqlitePool,
     id: i64,
 ) -> Result<DeleteDraftResponse, anyhow::Error> {
-    let deleted_row = sqlx::query_as!(
-        Post,
-        r#"
-DELETE FROM
+    let deleted_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Delete draft mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
     "Post"
+SET
+    "deleted_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    "scheduled_at" = NULL
 WHERE
     (
         "id" = $1
         AND "published" = FALSE
+        AND "deleted_at" IS NULL
     )
 RETURNING
     "id",
     "title",
+    "slug",
     "body",
-    "published"
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
      "#,
-        id,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
     )
-    .fetch_optional(db_pool)
     .await
     .map_err(|e| {
         tracing::error!("Failed to execute query: {e:?}");
@@ -234,55 +1035,380 @@ RETURNING
     }
 }
 
-/// Publishes draft matching `id`
-/// Returns `PublishResponse` with error, if the query yields no post matching `id`
-/// Successful publishing returns a `PublishResponse` with the updated post
+/// Returns drafts in the trash, most recently deleted first
 ///
 /// # Errors
 ///
 /// Errors if:
 ///  - unable to connect to database; or
 ///  - if SQL query fails.
-#[tracing::instrument(name = "Publish mutation", skip(db_pool))]
-pub async fn publish_mutation(
+#[tracing::instrument(name = "Trash query", skip(db_pool))]
+pub async fn trash_query(db_pool: &SqlitePool) -> Result<Vec<Post>, anyhow::Error> {
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Trash query", "SELECT", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+SELECT
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+FROM
+    "Post"
+WHERE
+    "deleted_at" IS NOT NULL
+ORDER BY
+    "deleted_at" DESC,
+    "id" DESC
+         "#,
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    Ok(rows)
+}
+
+/// Moves draft matching `id` out of the trash
+/// Returns `RestoreDraftResponse` with error, if the query yields no deleted draft matching `id`
+/// Successful restoration returns a `RestoreDraftResponse` with the restored draft
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Restore draft mutation", skip(db_pool))]
+pub async fn restore_draft_mutation(
     db_pool: &SqlitePool,
     id: i64,
-) -> Result<PublishResponse, anyhow::Error> {
-    let updated_row = sqlx::query_as!(
-        Post,
-        r#"
+) -> Result<RestoreDraftResponse, anyhow::Error> {
+    let restored_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Restore draft mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
 UPDATE
     "Post"
 SET
-    "published" = TRUE
+    "deleted_at" = NULL
 WHERE
-    ("id" = $1)
+    "id" = $1
+    AND "deleted_at" IS NOT NULL
 RETURNING
     "id",
     "title",
+    "slug",
     "body",
-    "published"
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
      "#,
-        id,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
     )
-    .fetch_optional(db_pool)
     .await
     .inspect_err(|err| {
         tracing::error!("Failed to execute query: {err:?}");
     })?;
 
-    match updated_row {
-        Some(value) => Ok(PublishResponse::PublishSuccessResponse(
-            PublishSuccessResponse { post: value },
+    match restored_row {
+        Some(value) => Ok(RestoreDraftResponse::RestoreDraftSuccessResponse(
+            RestoreDraftSuccessResponse { post: value },
         )),
-        None => Ok(PublishResponse::PublishErrorResponse(
-            PublishErrorResponse {
+        None => Ok(RestoreDraftResponse::RestoreDraftErrorResponse(
+            RestoreDraftErrorResponse {
                 error: UserInputError {
                     field: "id".to_string(),
-                    message: format!("Did not find draft post with id `{id}`"),
+                    message: format!("Did not find deleted draft with id `{id}`"),
                     received: id.to_string(),
                 },
             },
         )),
     }
 }
+
+/// Permanently removes drafts moved to the trash before `deleted_before`, returning the number of
+/// drafts removed
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Purge deleted drafts mutation", skip(db_pool))]
+pub async fn purge_deleted_drafts_mutation(
+    db_pool: &SqlitePool,
+    deleted_before: DateTime<Utc>,
+) -> Result<u64, anyhow::Error> {
+    // `julianday` normalises timestamps, so `deleted_before` compares correctly with stored values
+    let result = observe_query(
+        db_pool,
+        DatabaseQuery::new("Purge deleted drafts mutation", "DELETE", "Post"),
+        async |connection| {
+            sqlx::query!(
+                r#"
+DELETE FROM
+    "Post"
+WHERE
+    "deleted_at" IS NOT NULL
+    AND julianday("deleted_at") < julianday($1)
+     "#,
+                deleted_before
+            )
+            .execute(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run purge deleted drafts mutation")?;
+
+    Ok(result.rows_affected())
+}
+
+/// Returns the `published` state of the post matching `id`, or `None` if there is no such post
+pub(crate) async fn published_state(
+    db_pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<bool>, sqlx::Error> {
+    observe_query(
+        db_pool,
+        DatabaseQuery::new("Published state query", "SELECT", "Post"),
+        async |connection| {
+            sqlx::query_scalar!(
+                r#"
+SELECT
+    "published"
+FROM
+    "Post"
+WHERE
+    "id" = $1
+    AND "deleted_at" IS NULL
+     "#,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+}
+
+/// Publishes draft matching `id`
+/// Returns `PublishResponse` with error, if the query yields no post matching `id`, or if the post
+/// is already published
+/// Successful publishing returns a `PublishResponse` with the updated post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Publish mutation", skip(db_pool))]
+pub async fn publish_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+) -> Result<PublishResponse, anyhow::Error> {
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Publish mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "published" = TRUE,
+    "published_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    "updated_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    "scheduled_at" = NULL
+WHERE
+    (
+        "id" = $1
+        AND "published" = FALSE
+        AND "deleted_at" IS NULL
+    )
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    if let Some(value) = updated_row {
+        return Ok(PublishResponse::PublishSuccessResponse(
+            PublishSuccessResponse { post: value },
+        ));
+    }
+
+    let message = match published_state(db_pool, id).await? {
+        Some(true) => format!("Post with id `{id}` is already published"),
+        _ => format!("Did not find draft post with id `{id}`"),
+    };
+
+    Ok(PublishResponse::PublishErrorResponse(
+        PublishErrorResponse {
+            error: UserInputError {
+                field: "id".to_string(),
+                message,
+                received: id.to_string(),
+            },
+        },
+    ))
+}
+
+/// Returns published post matching `id` to draft
+/// Returns `UnpublishResponse` with error, if the query yields no post matching `id`, or if the
+/// post is not published
+/// Successful unpublishing returns an `UnpublishResponse` with the updated post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Unpublish mutation", skip(db_pool))]
+pub async fn unpublish_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+) -> Result<UnpublishResponse, anyhow::Error> {
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Unpublish mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "published" = FALSE,
+    "published_at" = NULL,
+    "updated_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+WHERE
+    (
+        "id" = $1
+        AND "published" = TRUE
+    )
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    if let Some(value) = updated_row {
+        return Ok(UnpublishResponse::UnpublishSuccessResponse(
+            UnpublishSuccessResponse { post: value },
+        ));
+    }
+
+    let message = match published_state(db_pool, id).await? {
+        Some(false) => format!("Post with id `{id}` is not published"),
+        _ => format!("Did not find published post with id `{id}`"),
+    };
+
+    Ok(UnpublishResponse::UnpublishErrorResponse(
+        UnpublishErrorResponse {
+            error: UserInputError {
+                field: "id".to_string(),
+                message,
+                received: id.to_string(),
+            },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slugify;
+
+    #[test]
+    fn slugify_replaces_punctuation_and_whitespace_with_single_hyphens() {
+        // arrange
+        let title = "  Hello, World!  Rust & GraphQL ";
+
+        // act
+        let outcome = slugify(title);
+
+        // assert
+        assert_eq!(outcome, "hello-world-rust-graphql");
+    }
+
+    #[test]
+    fn slugify_returns_fallback_when_title_has_no_ascii_alphanumerics() {
+        // arrange
+        let title = "¡¿?!";
+
+        // act
+        let outcome = slugify(title);
+
+        // assert
+        assert_eq!(outcome, "post");
+    }
+}
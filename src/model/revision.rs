@@ -0,0 +1,317 @@
+use anyhow::Context as _;
+use async_graphql::{SimpleObject, Union};
+use chrono::{DateTime, Utc};
+use similar::TextDiff;
+use sqlx::{Connection, SqlitePool};
+
+use crate::database::{DatabaseQuery, observe_query};
+
+use super::post::{Post, UserInputError, post_query};
+
+/// Snapshot of the content of a post, recorded whenever its title or body changes, including when
+/// the post is first created
+#[derive(Clone, Debug, PartialEq, SimpleObject)]
+pub struct PostRevision {
+    pub id: i64,
+
+    /// `id` of the post this is a revision of
+    pub post_id: i64,
+
+    pub title: String,
+    pub body: String,
+
+    /// Time the content was changed to this revision
+    pub created_at: DateTime<Utc>,
+}
+
+impl PostRevision {
+    /// Text compared by [`unified_diff`]: the title, then a blank line, then the body, always
+    /// ending in a newline
+    fn text(&self) -> String {
+        let mut text = format!("{}\n\n{}", self.title, self.body);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+/// Line-based unified diff of the content of revision `from` against revision `to`, where the
+/// title is compared as the first line.  Returns an empty string if the content is unchanged.
+#[must_use]
+pub fn unified_diff(from: &PostRevision, to: &PostRevision) -> String {
+    let from_text = from.text();
+    let to_text = to.text();
+
+    TextDiff::from_lines(&from_text, &to_text)
+        .unified_diff()
+        .header(
+            &format!("revision {}", from.id),
+            &format!("revision {}", to.id),
+        )
+        .to_string()
+}
+
+/// Response sent on valid restore revision mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RestoreRevisionSuccessResponse {
+    /// Post, with the content of the restored revision
+    pub post: Post,
+}
+
+/// Response sent on restore revision mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RestoreRevisionErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on restore revision mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum RestoreRevisionResponse {
+    RestoreRevisionSuccessResponse(RestoreRevisionSuccessResponse),
+    RestoreRevisionErrorResponse(RestoreRevisionErrorResponse),
+}
+
+/// Returns the revisions of the post matching `post_id`, newest first
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Post revisions query", skip(db_pool))]
+pub async fn post_revisions_query(
+    db_pool: &SqlitePool,
+    post_id: i64,
+) -> Result<Vec<PostRevision>, anyhow::Error> {
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Post revisions query", "SELECT", "PostRevision"),
+        async |connection| {
+            sqlx::query_as!(
+                PostRevision,
+                r#"
+SELECT
+    "id",
+    "post_id",
+    "title",
+    "body",
+    "created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "PostRevision"
+WHERE
+    "post_id" = $1
+ORDER BY
+    "id" DESC
+         "#,
+                post_id
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    Ok(rows)
+}
+
+/// Returns the revision matching `id` of the post matching `post_id`, or `None` if the post has no
+/// such revision
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Post revision query", skip(db_pool))]
+pub async fn post_revision_query(
+    db_pool: &SqlitePool,
+    post_id: i64,
+    id: i64,
+) -> Result<Option<PostRevision>, anyhow::Error> {
+    let row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Post revision query", "SELECT", "PostRevision"),
+        async |connection| {
+            sqlx::query_as!(
+                PostRevision,
+                r#"
+SELECT
+    "id",
+    "post_id",
+    "title",
+    "body",
+    "created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "PostRevision"
+WHERE
+    "id" = $1
+    AND "post_id" = $2
+         "#,
+                id,
+                post_id
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await?;
+
+    Ok(row)
+}
+
+/// Replaces the title and body of the post matching `post_id` with those of its revision matching
+/// `revision_id`.  Restoring records a new revision, in the same transaction, unless the content is
+/// unchanged.
+/// Returns `RestoreRevisionResponse` with error, if the query yields no post matching `post_id`,
+/// including deleted drafts, or no revision matching `revision_id` for the post
+/// Successful restoration returns a `RestoreRevisionResponse` with the updated post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Restore revision mutation", skip(db_pool))]
+pub async fn restore_revision_mutation(
+    db_pool: &SqlitePool,
+    post_id: i64,
+    revision_id: i64,
+) -> Result<RestoreRevisionResponse, anyhow::Error> {
+    if post_query(db_pool, post_id).await?.is_none() {
+        return Ok(missing_post_error(post_id));
+    }
+    let Some(revision) = post_revision_query(db_pool, post_id, revision_id).await? else {
+        return Ok(RestoreRevisionResponse::RestoreRevisionErrorResponse(
+            RestoreRevisionErrorResponse {
+                error: UserInputError {
+                    field: "revisionId".to_string(),
+                    message: format!(
+                        "Did not find revision with id `{revision_id}` for post with id \
+                         `{post_id}`"
+                    ),
+                    received: revision_id.to_string(),
+                },
+            },
+        ));
+    };
+
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Restore revision mutation", "UPDATE", "Post"),
+        async |connection| {
+            // the `Post_revision_update` trigger records the new revision within the transaction
+            let mut transaction = connection.begin().await?;
+            let row = sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "title" = $2,
+    "body" = $3,
+    "updated_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+WHERE
+    "id" = $1
+    AND "deleted_at" IS NULL
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                post_id,
+                revision.title,
+                revision.body
+            )
+            .fetch_optional(&mut *transaction)
+            .await?;
+            transaction.commit().await?;
+
+            Ok(row)
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run restore revision mutation")?;
+
+    // the draft may have been deleted since it was checked
+    let Some(post) = updated_row else {
+        return Ok(missing_post_error(post_id));
+    };
+
+    Ok(RestoreRevisionResponse::RestoreRevisionSuccessResponse(
+        RestoreRevisionSuccessResponse { post },
+    ))
+}
+
+fn missing_post_error(post_id: i64) -> RestoreRevisionResponse {
+    RestoreRevisionResponse::RestoreRevisionErrorResponse(RestoreRevisionErrorResponse {
+        error: UserInputError {
+            field: "postId".to_string(),
+            message: format!("Did not find post with id `{post_id}`"),
+            received: post_id.to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{PostRevision, unified_diff};
+
+    fn revision(id: i64, title: &str, body: &str) -> PostRevision {
+        PostRevision {
+            id,
+            post_id: 1,
+            title: title.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn unified_diff_compares_title_and_body_lines() {
+        // arrange
+        let from = revision(1, "Title", "First line\nSecond line");
+        let to = revision(2, "New Title", "First line\nChanged line");
+
+        // act
+        let outcome = unified_diff(&from, &to);
+
+        // assert
+        assert_eq!(
+            outcome,
+            "--- revision 1\n+++ revision 2\n@@ -1,4 +1,4 @@\n-Title\n+New Title\n \n \
+             First line\n-Second line\n+Changed line\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_is_empty_for_unchanged_content() {
+        // arrange
+        let from = revision(1, "Title", "Body\n");
+        let to = revision(2, "Title", "Body");
+
+        // act
+        let outcome = unified_diff(&from, &to);
+
+        // assert
+        assert_eq!(outcome, "");
+    }
+}
@@ -0,0 +1,290 @@
+use anyhow::Context as _;
+use async_graphql::{SimpleObject, Union};
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, SqlitePool};
+
+use crate::database::{DatabaseQuery, observe_query};
+
+use super::post::{Post, UserInputError, published_state};
+
+/// Response sent on valid schedule publish mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct SchedulePublishSuccessResponse {
+    /// Draft, with the scheduled publish time set
+    pub post: Post,
+}
+
+/// Response sent on schedule publish mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct SchedulePublishErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on schedule publish mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum SchedulePublishResponse {
+    SchedulePublishSuccessResponse(SchedulePublishSuccessResponse),
+    SchedulePublishErrorResponse(SchedulePublishErrorResponse),
+}
+
+/// Response sent on valid cancel scheduled publish mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct CancelScheduledPublishSuccessResponse {
+    /// Draft, no longer scheduled for publishing
+    pub post: Post,
+}
+
+/// Response sent on cancel scheduled publish mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct CancelScheduledPublishErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on cancel scheduled publish mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum CancelScheduledPublishResponse {
+    CancelScheduledPublishSuccessResponse(CancelScheduledPublishSuccessResponse),
+    CancelScheduledPublishErrorResponse(CancelScheduledPublishErrorResponse),
+}
+
+/// Schedules draft matching `id` to be published at `at`, replacing any earlier schedule
+/// Returns `SchedulePublishResponse` with error, if `at` is not in the future, the query yields no
+/// draft matching `id`, or the post is already published
+/// Successful scheduling returns a `SchedulePublishResponse` with the scheduled draft
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Schedule publish mutation", skip(db_pool))]
+pub async fn schedule_publish_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+    at: DateTime<Utc>,
+) -> Result<SchedulePublishResponse, anyhow::Error> {
+    if at <= Utc::now() {
+        return Ok(SchedulePublishResponse::SchedulePublishErrorResponse(
+            SchedulePublishErrorResponse {
+                error: UserInputError {
+                    field: "at".to_string(),
+                    message: String::from("Scheduled publish time should be in the future"),
+                    received: at.to_rfc3339(),
+                },
+            },
+        ));
+    }
+
+    // store the time in the same format as other timestamps, since publishing copies it to
+    // `published_at`, which is compared as text
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Schedule publish mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "scheduled_at" = strftime('%Y-%m-%dT%H:%M:%fZ', $2)
+WHERE
+    "id" = $1
+    AND "published" = FALSE
+    AND "deleted_at" IS NULL
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                id,
+                at
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    if let Some(value) = updated_row {
+        return Ok(SchedulePublishResponse::SchedulePublishSuccessResponse(
+            SchedulePublishSuccessResponse { post: value },
+        ));
+    }
+
+    let message = match published_state(db_pool, id).await? {
+        Some(true) => format!("Post with id `{id}` is already published"),
+        _ => format!("Did not find draft post with id `{id}`"),
+    };
+
+    Ok(SchedulePublishResponse::SchedulePublishErrorResponse(
+        SchedulePublishErrorResponse {
+            error: UserInputError {
+                field: "id".to_string(),
+                message,
+                received: id.to_string(),
+            },
+        },
+    ))
+}
+
+/// Clears the scheduled publish time of draft matching `id`
+/// Returns `CancelScheduledPublishResponse` with error, if the query yields no draft matching
+/// `id`, or the draft is not scheduled for publishing
+/// Successful cancellation returns a `CancelScheduledPublishResponse` with the draft
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Cancel scheduled publish mutation", skip(db_pool))]
+pub async fn cancel_scheduled_publish_mutation(
+    db_pool: &SqlitePool,
+    id: i64,
+) -> Result<CancelScheduledPublishResponse, anyhow::Error> {
+    let updated_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Cancel scheduled publish mutation", "UPDATE", "Post"),
+        async |connection| {
+            sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "scheduled_at" = NULL
+WHERE
+    "id" = $1
+    AND "published" = FALSE
+    AND "deleted_at" IS NULL
+    AND "scheduled_at" IS NOT NULL
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                id,
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    if let Some(value) = updated_row {
+        return Ok(
+            CancelScheduledPublishResponse::CancelScheduledPublishSuccessResponse(
+                CancelScheduledPublishSuccessResponse { post: value },
+            ),
+        );
+    }
+
+    let message = match published_state(db_pool, id).await? {
+        Some(false) => format!("Draft post with id `{id}` is not scheduled for publishing"),
+        Some(true) => format!("Post with id `{id}` is already published"),
+        None => format!("Did not find draft post with id `{id}`"),
+    };
+
+    Ok(
+        CancelScheduledPublishResponse::CancelScheduledPublishErrorResponse(
+            CancelScheduledPublishErrorResponse {
+                error: UserInputError {
+                    field: "id".to_string(),
+                    message,
+                    received: id.to_string(),
+                },
+            },
+        ),
+    )
+}
+
+/// Publishes every draft scheduled to be published at, or before, `now`, in a single transaction,
+/// returning the published posts.  Each post's `published_at` is set to its scheduled time, so
+/// drafts published late, for example after the app was stopped, are ordered as scheduled.
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Publish due posts mutation", skip(db_pool))]
+pub async fn publish_due_posts_mutation(
+    db_pool: &SqlitePool,
+    now: DateTime<Utc>,
+) -> Result<Vec<Post>, anyhow::Error> {
+    // `julianday` normalises timestamps, so `now` compares correctly with stored values
+    let published_rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Publish due posts mutation", "UPDATE", "Post"),
+        async |connection| {
+            let mut transaction = connection.begin().await?;
+            let rows = sqlx::query_as!(
+                Post,
+                r#"
+UPDATE
+    "Post"
+SET
+    "published" = TRUE,
+    "published_at" = "scheduled_at",
+    "updated_at" = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+    "scheduled_at" = NULL
+WHERE
+    "published" = FALSE
+    AND "deleted_at" IS NULL
+    AND "scheduled_at" IS NOT NULL
+    AND julianday("scheduled_at") <= julianday($1)
+RETURNING
+    "id",
+    "title",
+    "slug",
+    "body",
+    "published",
+    "created_at" AS "created_at: DateTime<Utc>",
+    "updated_at" AS "updated_at: DateTime<Utc>",
+    "published_at" AS "published_at: DateTime<Utc>",
+    "author_id",
+    "deleted_at" AS "deleted_at: DateTime<Utc>",
+    "scheduled_at" AS "scheduled_at: DateTime<Utc>"
+     "#,
+                now
+            )
+            .fetch_all(&mut *transaction)
+            .await?;
+            transaction.commit().await?;
+
+            Ok(rows)
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run publish due posts mutation")?;
+
+    Ok(published_rows)
+}
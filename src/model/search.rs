@@ -0,0 +1,254 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::database::{DatabaseQuery, observe_query};
+
+use super::post::Post;
+
+/// Largest number of search results returned in a single page, also used when the client does
+/// not request `first`
+pub const MAX_SEARCH_PAGE_SIZE: usize = 50;
+
+/// Control characters `SQLite` places either side of matched terms, which are replaced with
+/// `<mark>` tags once the rest of the text is HTML-escaped
+const HIGHLIGHT_START: &str = "\u{2}";
+const HIGHLIGHT_END: &str = "\u{3}";
+
+/// Post matching a search query, with matched terms highlighted
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct PostSearchResult {
+    /// Matching post
+    pub post: Post,
+
+    /// HTML-escaped post title, with matched terms wrapped in `<mark>` tags
+    pub title_highlight: String,
+
+    /// HTML-escaped short extract of the post body around the best match, with matched terms
+    /// wrapped in `<mark>` tags
+    pub snippet: String,
+
+    /// BM25 relevance score, where lower values are more relevant
+    pub rank: f64,
+}
+
+/// Page of search results, ordered by relevance
+#[derive(Debug, PartialEq)]
+pub struct PostSearchPage {
+    pub results: Vec<PostSearchResult>,
+    pub has_next_page: bool,
+}
+
+struct PostSearchRow {
+    id: i64,
+    title: String,
+    slug: String,
+    body: String,
+    published: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    published_at: Option<DateTime<Utc>>,
+    author_id: Option<i64>,
+    deleted_at: Option<DateTime<Utc>>,
+    scheduled_at: Option<DateTime<Utc>>,
+    title_highlight: String,
+    snippet: String,
+    rank: f64,
+}
+
+impl From<PostSearchRow> for PostSearchResult {
+    fn from(row: PostSearchRow) -> Self {
+        let PostSearchRow {
+            id,
+            title,
+            slug,
+            body,
+            published,
+            created_at,
+            updated_at,
+            published_at,
+            author_id,
+            deleted_at,
+            scheduled_at,
+            title_highlight,
+            snippet,
+            rank,
+        } = row;
+
+        Self {
+            post: Post {
+                id,
+                title,
+                slug,
+                body,
+                published,
+                created_at,
+                updated_at,
+                published_at,
+                author_id,
+                deleted_at,
+                scheduled_at,
+            },
+            title_highlight: highlight_html(&title_highlight),
+            snippet: highlight_html(&snippet),
+            rank,
+        }
+    }
+}
+
+/// HTML-escape `text`, highlighted by `SQLite`, then wrap matched terms in `<mark>` tags.  Post text
+/// is escaped, so clients can render the result as HTML, and any markup in a post is shown
+/// literally, rather than run.
+fn highlight_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            '\u{2}' => html.push_str("<mark>"),
+            '\u{3}' => html.push_str("</mark>"),
+            _ => html.push(character),
+        }
+    }
+
+    html
+}
+
+/// Convert free text from a user into an FTS5 query, matching posts which contain every term.
+/// Terms are quoted, so FTS5 operators and syntax characters in user input are matched literally,
+/// rather than causing query syntax errors.
+fn match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns a page of posts matching `query`, most relevant first, skipping the first `offset`
+/// matches.  Drafts are only included when `include_drafts` is `true`.
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Search posts query", skip(db_pool))]
+pub async fn search_posts_query(
+    db_pool: &SqlitePool,
+    query: &str,
+    include_drafts: bool,
+    offset: usize,
+    first: Option<usize>,
+) -> Result<PostSearchPage, anyhow::Error> {
+    let expression = match_expression(query);
+    if expression.is_empty() {
+        return Ok(PostSearchPage {
+            results: Vec::new(),
+            has_next_page: false,
+        });
+    }
+
+    let page_size = first
+        .unwrap_or(MAX_SEARCH_PAGE_SIZE)
+        .min(MAX_SEARCH_PAGE_SIZE);
+
+    // fetch one extra row to find out if there is another page beyond this one
+    let limit = i64::try_from(page_size + 1)?;
+    let offset = i64::try_from(offset)?;
+    let mut rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Search posts query", "SELECT", "PostSearch"),
+        async |connection| {
+            sqlx::query_as!(
+                PostSearchRow,
+                r#"
+SELECT
+    "Post"."id" AS "id!",
+    "Post"."title" AS "title!",
+    "Post"."slug" AS "slug!",
+    "Post"."body" AS "body!",
+    "Post"."published" AS "published!: bool",
+    "Post"."created_at" AS "created_at!: DateTime<Utc>",
+    "Post"."updated_at" AS "updated_at!: DateTime<Utc>",
+    "Post"."published_at" AS "published_at: DateTime<Utc>",
+    "Post"."author_id",
+    "Post"."deleted_at" AS "deleted_at: DateTime<Utc>",
+    "Post"."scheduled_at" AS "scheduled_at: DateTime<Utc>",
+    highlight("PostSearch", 0, $3, $4) AS "title_highlight!: String",
+    snippet("PostSearch", 1, $3, $4, '…', 16) AS "snippet!: String",
+    bm25("PostSearch") AS "rank!: f64"
+FROM
+    "PostSearch"
+    INNER JOIN "Post" ON "Post"."id" = "PostSearch"."rowid"
+WHERE
+    "PostSearch" MATCH $1
+    AND "Post"."deleted_at" IS NULL
+    AND ("Post"."published" = TRUE OR $2)
+ORDER BY
+    bm25("PostSearch") ASC,
+    "Post"."id" ASC
+LIMIT
+    $5
+OFFSET
+    $6
+         "#,
+                expression,
+                include_drafts,
+                HIGHLIGHT_START,
+                HIGHLIGHT_END,
+                limit,
+                offset
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    let has_next_page = rows.len() > page_size;
+    rows.truncate(page_size);
+
+    Ok(PostSearchPage {
+        results: rows.into_iter().map(PostSearchResult::from).collect(),
+        has_next_page,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight_html, match_expression};
+
+    #[test]
+    fn match_expression_quotes_each_term() {
+        // arrange
+        let query = r#" rust  "graph"ql OR* "#;
+
+        // act
+        let outcome = match_expression(query);
+
+        // assert
+        assert_eq!(outcome, r#""rust" """graph""ql" "OR*""#);
+    }
+
+    #[test]
+    fn highlight_html_escapes_text_and_marks_matched_terms() {
+        // arrange
+        let text = "<script>alert(\"\u{2}Rust\u{3}\")</script> & 'more'";
+
+        // act
+        let outcome = highlight_html(text);
+
+        // assert
+        assert_eq!(
+            outcome,
+            "&lt;script&gt;alert(&quot;<mark>Rust</mark>&quot;)&lt;/script&gt; &amp; &#39;more&#39;"
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context as _;
+use async_graphql::{SimpleObject, Union, dataloader::Loader};
+use chrono::{DateTime, Utc};
+use sqlx::{Connection, SqlitePool};
+
+use crate::database::{DatabaseQuery, json_id_array, observe_query};
+
+use super::post::{Post, UserInputError, post_query, slug_words};
+
+/// Longest tag name accepted, after normalising to a slug
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Topic used to organise posts
+#[derive(Clone, Debug, PartialEq, SimpleObject)]
+pub struct Tag {
+    pub id: i64,
+
+    /// Name, normalised to a URL-friendly slug
+    pub name: String,
+
+    /// Time the tag was first added to a post
+    pub created_at: DateTime<Utc>,
+}
+
+/// Tag added to the post with `post_id`
+struct PostTagRow {
+    post_id: i64,
+    id: i64,
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<PostTagRow> for Tag {
+    fn from(row: PostTagRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Normalise tag `name` to a slug, so `Rust Lang` and `rust-lang` name the same tag
+///
+/// # Errors
+///
+/// Errors if the normalised name is empty, or longer than [`MAX_TAG_LENGTH`].
+pub fn normalise_tag_name(name: &str) -> Result<String, UserInputError> {
+    let slug = slug_words(name);
+    let message = if slug.is_empty() {
+        String::from("Tag name should include at least one ASCII letter or digit")
+    } else if slug.len() > MAX_TAG_LENGTH {
+        format!("Tag name should be at most {MAX_TAG_LENGTH} characters long")
+    } else {
+        return Ok(slug);
+    };
+
+    Err(UserInputError {
+        field: "name".to_string(),
+        message,
+        received: name.to_string(),
+    })
+}
+
+/// Response sent on valid add tag mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct AddTagSuccessResponse {
+    /// Post, with the added tag
+    pub post: Post,
+}
+
+/// Response sent on add tag mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct AddTagErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on add tag mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum AddTagResponse {
+    AddTagSuccessResponse(AddTagSuccessResponse),
+    AddTagErrorResponse(AddTagErrorResponse),
+}
+
+/// Response sent on valid remove tag mutation
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RemoveTagSuccessResponse {
+    /// Post, without the removed tag
+    pub post: Post,
+}
+
+/// Response sent on remove tag mutation with validation issues identified
+#[derive(Debug, PartialEq, SimpleObject)]
+pub struct RemoveTagErrorResponse {
+    /// User input error details
+    pub error: UserInputError,
+}
+
+/// Union of responses sent on remove tag mutation
+#[derive(Debug, PartialEq, Union)]
+pub enum RemoveTagResponse {
+    RemoveTagSuccessResponse(RemoveTagSuccessResponse),
+    RemoveTagErrorResponse(RemoveTagErrorResponse),
+}
+
+/// Batches tag lookups by post `id`, so resolving the tags of every post in a list runs a single
+/// query
+pub struct TagsLoader {
+    db_pool: SqlitePool,
+}
+
+impl TagsLoader {
+    #[must_use]
+    pub fn new(db_pool: SqlitePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl Loader<i64> for TagsLoader {
+    type Value = Vec<Tag>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let rows = post_tag_rows(&self.db_pool, keys).await.map_err(Arc::new)?;
+
+        let mut tags: HashMap<i64, Vec<Tag>> = HashMap::new();
+        for row in rows {
+            tags.entry(row.post_id).or_default().push(Tag::from(row));
+        }
+
+        Ok(tags)
+    }
+}
+
+/// Returns tags added to posts matching any of `post_ids`, ordered by name
+async fn post_tag_rows(
+    db_pool: &SqlitePool,
+    post_ids: &[i64],
+) -> Result<Vec<PostTagRow>, anyhow::Error> {
+    let post_ids = json_id_array(post_ids);
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Post tags query", "SELECT", "PostTag"),
+        async |connection| {
+            sqlx::query_as!(
+                PostTagRow,
+                r#"
+SELECT
+    "PostTag"."post_id",
+    "Tag"."id",
+    "Tag"."name",
+    "Tag"."created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "PostTag"
+    INNER JOIN "Tag" ON "Tag"."id" = "PostTag"."tag_id"
+WHERE
+    "PostTag"."post_id" IN (
+        SELECT
+            "value"
+        FROM
+            json_each($1)
+    )
+ORDER BY
+    "Tag"."name" ASC
+         "#,
+                post_ids
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    Ok(rows)
+}
+
+/// Adds the tag `name`, normalised to a slug, to the post matching `post_id`, creating the tag if
+/// it does not yet exist.  Adding a tag the post already has succeeds, leaving the post unchanged.
+/// Returns `AddTagResponse` with error, if the query yields no post matching `post_id`, or `name`
+/// is not a valid tag name
+/// Successful addition returns an `AddTagResponse` with the post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Add tag mutation", skip(db_pool))]
+pub async fn add_tag_mutation(
+    db_pool: &SqlitePool,
+    post_id: i64,
+    name: &str,
+) -> Result<AddTagResponse, anyhow::Error> {
+    let tag_name = match normalise_tag_name(name) {
+        Ok(value) => value,
+        Err(error) => {
+            return Ok(AddTagResponse::AddTagErrorResponse(AddTagErrorResponse {
+                error,
+            }));
+        }
+    };
+    let Some(post) = post_query(db_pool, post_id).await? else {
+        return Ok(AddTagResponse::AddTagErrorResponse(AddTagErrorResponse {
+            error: missing_post_error(post_id),
+        }));
+    };
+
+    observe_query(
+        db_pool,
+        DatabaseQuery::new("Add tag mutation", "INSERT", "PostTag"),
+        async |connection| {
+            let mut transaction = connection.begin().await?;
+            // the no-op update makes `RETURNING` yield the `id` of an existing tag too
+            let tag_id = sqlx::query_scalar!(
+                r#"
+INSERT INTO
+    "Tag" ("name")
+VALUES
+    ($1)
+ON CONFLICT ("name") DO UPDATE
+SET
+    "name" = excluded."name"
+RETURNING
+    "id"
+     "#,
+                tag_name
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+            sqlx::query!(
+                r#"
+INSERT INTO
+    "PostTag" ("post_id", "tag_id")
+VALUES
+    ($1, $2)
+ON CONFLICT DO NOTHING
+     "#,
+                post_id,
+                tag_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            transaction.commit().await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run add tag mutation")?;
+
+    Ok(AddTagResponse::AddTagSuccessResponse(
+        AddTagSuccessResponse { post },
+    ))
+}
+
+/// Removes the tag `name`, normalised to a slug, from the post matching `post_id`
+/// Returns `RemoveTagResponse` with error, if the query yields no post matching `post_id`, or the
+/// post does not have the tag
+/// Successful removal returns a `RemoveTagResponse` with the post
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Remove tag mutation", skip(db_pool))]
+pub async fn remove_tag_mutation(
+    db_pool: &SqlitePool,
+    post_id: i64,
+    name: &str,
+) -> Result<RemoveTagResponse, anyhow::Error> {
+    let tag_name = match normalise_tag_name(name) {
+        Ok(value) => value,
+        Err(error) => {
+            return Ok(RemoveTagResponse::RemoveTagErrorResponse(
+                RemoveTagErrorResponse { error },
+            ));
+        }
+    };
+    let Some(post) = post_query(db_pool, post_id).await? else {
+        return Ok(RemoveTagResponse::RemoveTagErrorResponse(
+            RemoveTagErrorResponse {
+                error: missing_post_error(post_id),
+            },
+        ));
+    };
+
+    let result = observe_query(
+        db_pool,
+        DatabaseQuery::new("Remove tag mutation", "DELETE", "PostTag"),
+        async |connection| {
+            sqlx::query!(
+                r#"
+DELETE FROM
+    "PostTag"
+WHERE
+    "post_id" = $1
+    AND "tag_id" IN (
+        SELECT
+            "id"
+        FROM
+            "Tag"
+        WHERE
+            "name" = $2
+    )
+     "#,
+                post_id,
+                tag_name
+            )
+            .execute(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run remove tag mutation")?;
+
+    if result.rows_affected() == 0 {
+        return Ok(RemoveTagResponse::RemoveTagErrorResponse(
+            RemoveTagErrorResponse {
+                error: UserInputError {
+                    field: "name".to_string(),
+                    message: format!("Post with id `{post_id}` is not tagged `{tag_name}`"),
+                    received: name.to_string(),
+                },
+            },
+        ));
+    }
+
+    Ok(RemoveTagResponse::RemoveTagSuccessResponse(
+        RemoveTagSuccessResponse { post },
+    ))
+}
+
+fn missing_post_error(post_id: i64) -> UserInputError {
+    UserInputError {
+        field: "postId".to_string(),
+        message: format!("Did not find post with id `{post_id}`"),
+        received: post_id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalise_tag_name;
+
+    #[test]
+    fn normalise_tag_name_converts_name_to_slug() {
+        // arrange
+        let name = "  Rust & GraphQL ";
+
+        // act
+        let outcome = normalise_tag_name(name);
+
+        // assert
+        assert_eq!(outcome, Ok(String::from("rust-graphql")));
+    }
+
+    #[test]
+    fn normalise_tag_name_rejects_empty_and_long_names() {
+        // arrange
+        let empty = "¡¿?!";
+        let long = "a".repeat(33);
+
+        // act
+        let empty_outcome = normalise_tag_name(empty).unwrap_err();
+        let long_outcome = normalise_tag_name(&long).unwrap_err();
+
+        // assert
+        assert_eq!(empty_outcome.field, "name");
+        assert_eq!(empty_outcome.received, empty);
+        assert_eq!(
+            long_outcome.message,
+            "Tag name should be at most 32 characters long"
+        );
+    }
+}
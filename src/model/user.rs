@@ -0,0 +1,208 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context as _;
+use async_graphql::{ComplexObject, Context, SimpleObject, dataloader::Loader};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::database::{DatabaseQuery, json_id_array, observe_query};
+
+use super::{DraftsGuard, PostConnection, post::PostFilter, post_connection_query};
+
+#[derive(Clone, Debug, PartialEq, SimpleObject)]
+#[graphql(complex)]
+pub struct User {
+    pub id: i64,
+
+    /// Display name, shown as the author of posts
+    pub name: String,
+
+    /// Time the user was created
+    pub created_at: DateTime<Utc>,
+}
+
+#[ComplexObject]
+impl User {
+    /// Returns a page of published posts written by this user, newest first
+    async fn posts(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<PostConnection> {
+        let filter = PostFilter {
+            author_id: Some(self.id),
+            ..PostFilter::default()
+        };
+
+        post_connection_query(ctx, true, filter, (after, before, first, last)).await
+    }
+
+    /// Returns a page of draft posts written by this user, ordered by `id`.  Only available to
+    /// admins and the user themself.
+    #[graphql(guard = "DraftsGuard::author(self.id)")]
+    async fn drafts(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<PostConnection> {
+        let filter = PostFilter {
+            author_id: Some(self.id),
+            ..PostFilter::default()
+        };
+
+        post_connection_query(ctx, false, filter, (after, before, first, last)).await
+    }
+}
+
+/// Batches user lookups by `id`, so resolving the author of every post in a list runs a single
+/// query, rather than one query per post
+pub struct UserLoader {
+    db_pool: SqlitePool,
+}
+
+impl UserLoader {
+    #[must_use]
+    pub fn new(db_pool: SqlitePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl Loader<i64> for UserLoader {
+    type Value = User;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let users = users_query(&self.db_pool, keys).await.map_err(Arc::new)?;
+
+        Ok(users.into_iter().map(|user| (user.id, user)).collect())
+    }
+}
+
+/// Creates a new user with display name `name`
+/// Successful creation returns the created user
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Create user mutation", skip(db_pool))]
+pub async fn create_user_mutation(db_pool: &SqlitePool, name: &str) -> Result<User, anyhow::Error> {
+    let inserted_row = observe_query(
+        db_pool,
+        DatabaseQuery::new("Create user mutation", "INSERT", "User"),
+        async |connection| {
+            sqlx::query_as!(
+                User,
+                r#"
+INSERT INTO
+    "User" ("name")
+VALUES
+    ($1)
+RETURNING
+    "id",
+    "name",
+    "created_at" AS "created_at: DateTime<Utc>"
+"#,
+                name
+            )
+            .fetch_one(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })
+    .context("run create user mutation")?;
+
+    Ok(inserted_row)
+}
+
+/// Returns the user matching `id`, or `None` if there is no such user
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "User query", skip(db_pool))]
+pub async fn user_query(db_pool: &SqlitePool, id: i64) -> Result<Option<User>, anyhow::Error> {
+    let row = observe_query(
+        db_pool,
+        DatabaseQuery::new("User query", "SELECT", "User"),
+        async |connection| {
+            sqlx::query_as!(
+                User,
+                r#"
+SELECT
+    "id",
+    "name",
+    "created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "User"
+WHERE
+    "id" = $1
+         "#,
+                id
+            )
+            .fetch_optional(connection)
+            .await
+        },
+    )
+    .await?;
+
+    Ok(row)
+}
+
+/// Returns users matching any of `ids`, in no particular order.  Ids with no matching user are
+/// skipped.
+///
+/// # Errors
+///
+/// Errors if:
+///  - unable to connect to database; or
+///  - if SQL query fails.
+#[tracing::instrument(name = "Users query", skip(db_pool))]
+pub async fn users_query(db_pool: &SqlitePool, ids: &[i64]) -> Result<Vec<User>, anyhow::Error> {
+    let ids = json_id_array(ids);
+    let rows = observe_query(
+        db_pool,
+        DatabaseQuery::new("Users query", "SELECT", "User"),
+        async |connection| {
+            sqlx::query_as!(
+                User,
+                r#"
+SELECT
+    "id",
+    "name",
+    "created_at" AS "created_at: DateTime<Utc>"
+FROM
+    "User"
+WHERE
+    "id" IN (
+        SELECT
+            "value"
+        FROM
+            json_each($1)
+    )
+         "#,
+                ids
+            )
+            .fetch_all(connection)
+            .await
+        },
+    )
+    .await
+    .inspect_err(|err| {
+        tracing::error!("Failed to execute query: {err:?}");
+    })?;
+
+    Ok(rows)
+}
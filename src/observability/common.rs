@@ -1,20 +1,109 @@
 use std::{env, sync::OnceLock};
 
 use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{
-    Resource, error::OTelSdkError, logs::SdkLoggerProvider, metrics::SdkMeterProvider,
-    trace::SdkTracerProvider,
+    Resource,
+    error::OTelSdkError,
+    logs::SdkLoggerProvider,
+    metrics::SdkMeterProvider,
+    trace::{Sampler, SdkTracerProvider},
 };
 use tracing::Level;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 use ulid::Ulid;
 
-use super::{logging::init_logs, metrics::init_metrics, tracing::init_tracing};
+use super::{
+    logging::init_logs,
+    metrics::{init_metrics, init_prometheus_metrics},
+    tracing::{init_propagator, init_tracing, sampler_from_env},
+};
+
+/// Transport used to export traces, metrics and logs to the OpenTelemetry collector
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC, using tonic
+    #[default]
+    Grpc,
+
+    /// OTLP over HTTP, with protobuf-encoded bodies
+    HttpProtobuf,
+}
+
+impl OtlpProtocol {
+    /// Read the protocol from `OTEL_EXPORTER_OTLP_PROTOCOL`, defaulting to gRPC
+    ///
+    /// # Panics
+    ///
+    /// Panics if `OTEL_EXPORTER_OTLP_PROTOCOL` exists and is not either `grpc` or `http/protobuf`.
+    #[must_use]
+    pub fn from_env() -> Self {
+        match env::var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() {
+            Err(_) | Ok("grpc") => Self::Grpc,
+            Ok("http/protobuf") => Self::HttpProtobuf,
+            Ok(value) => panic!(
+                "`OTEL_EXPORTER_OTLP_PROTOCOL` env variable should be either `grpc` or \
+                 `http/protobuf`, not `{value}`"
+            ),
+        }
+    }
+
+    /// Collector port the protocol listens on by default
+    fn default_port(self) -> &'static str {
+        match self {
+            Self::Grpc => "4317",
+            Self::HttpProtobuf => "4318",
+        }
+    }
+}
 
+/// OpenTelemetry settings, shared by traces, metrics and logs.  Exporter headers, compression and
+/// timeout are read from the standard `OTEL_EXPORTER_OTLP_*` env variables by the exporters
+/// themselves.
 pub struct OpenTelemetryConfig {
     pub opentelemetry_agent_host: String,
     pub opentelemetry_agent_port: String,
     pub service_name: String,
+
+    /// Transport used to export all signals
+    pub protocol: OtlpProtocol,
+
+    /// Base collector URL built from `OPENTELEMETRY_AGENT_HOST` and `OPENTELEMETRY_AGENT_PORT`,
+    /// or `None` when `OTEL_EXPORTER_OTLP_ENDPOINT` is set and left to the exporters
+    pub endpoint: Option<String>,
+
+    /// Sampler deciding which traces are recorded
+    pub sampler: Sampler,
+}
+
+impl OpenTelemetryConfig {
+    /// Collector URL for `signal`, one of `traces`, `metrics` or `logs`, from the agent settings.
+    /// OTLP/HTTP collectors serve each signal on its own path, while gRPC collectors serve every
+    /// signal at the base URL.  `None` when the standard `OTEL_EXPORTER_OTLP_ENDPOINT` or
+    /// per-signal `OTEL_EXPORTER_OTLP_<SIGNAL>_ENDPOINT` env variable is set, since an endpoint
+    /// passed to an exporter takes precedence over both.
+    #[must_use]
+    pub fn signal_endpoint(&self, signal: &str) -> Option<String> {
+        let signal_variable = format!("OTEL_EXPORTER_OTLP_{}_ENDPOINT", signal.to_uppercase());
+        if env::var_os(signal_variable).is_some() {
+            return None;
+        }
+
+        let endpoint = self.endpoint.as_deref()?;
+        Some(match self.protocol {
+            OtlpProtocol::Grpc => endpoint.to_string(),
+            OtlpProtocol::HttpProtobuf => format!("{}/v1/{signal}", endpoint.trim_end_matches('/')),
+        })
+    }
+
+    /// Set the `signal` exporter endpoint on `builder`, when it is not left to the exporter's own
+    /// env variables
+    pub(super) fn with_signal_endpoint<B: WithExportConfig>(&self, builder: B, signal: &str) -> B {
+        match self.signal_endpoint(signal) {
+            Some(endpoint) => builder.with_endpoint(endpoint),
+            None => builder,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -47,17 +136,20 @@ impl OpenTelemetryProviders {
 }
 
 /// Initialise opentelemetry if the `OPENTELEMETRY_ENABLED` env variable is set to true, otherwise
-/// initialise basic terminal logging.
+/// initialise basic terminal logging.  Metrics are recorded for the Prometheus scrape endpoint
+/// either way, when `PROMETHEUS_ENABLED` is set to true, and W3C trace context is read from
+/// incoming request headers either way, so responses report the caller's trace id.
 ///
 /// # Panics
 ///
-/// Panics if `OPENTELEMETRY_ENABLED` environment variable exists and is not either `true` or
-/// `false`.
+/// Panics if `OPENTELEMETRY_ENABLED` or `PROMETHEUS_ENABLED` environment variables exist and are
+/// not either `true` or `false`.
 pub fn initialise_observability() -> Option<OpenTelemetryProviders> {
     let opentelemetry_enabled: bool = env::var("OPENTELEMETRY_ENABLED")
         .unwrap_or_else(|_| "false".into())
         .parse()
         .expect("`OPENTELEMETRY_ENABLED` env variable should be either `true` or `false`");
+    init_propagator();
 
     if opentelemetry_enabled {
         Some(OpenTelemetryProviders::new())
@@ -71,19 +163,40 @@ pub fn initialise_observability() -> Option<OpenTelemetryProviders> {
             .init();
 
         tracing::info!("Tracing subscriber created and initialised");
+        if init_prometheus_metrics(&get_opentelemetry_config_from_env()).is_some() {
+            tracing::info!("Prometheus metrics initialised");
+        }
         None
     }
 }
 
+/// Read OpenTelemetry settings from env variables.  Unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set,
+/// the collector endpoint is built from `OPENTELEMETRY_AGENT_HOST` and `OPENTELEMETRY_AGENT_PORT`,
+/// where the port defaults to the standard one for the protocol.
+///
+/// # Panics
+///
+/// Panics if `OTEL_EXPORTER_OTLP_PROTOCOL`, `OTEL_TRACES_SAMPLER` or `OTEL_TRACES_SAMPLER_ARG`
+/// exist, but are not valid.
 #[must_use]
 pub fn get_opentelemetry_config_from_env() -> OpenTelemetryConfig {
+    let protocol = OtlpProtocol::from_env();
+    let opentelemetry_agent_host =
+        env::var("OPENTELEMETRY_AGENT_HOST").unwrap_or_else(|_| "http://localhost".into());
+    let opentelemetry_agent_port =
+        env::var("OPENTELEMETRY_AGENT_PORT").unwrap_or_else(|_| protocol.default_port().into());
+    let endpoint = env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .is_none()
+        .then(|| format!("{opentelemetry_agent_host}:{opentelemetry_agent_port}"));
+
     OpenTelemetryConfig {
-        opentelemetry_agent_host: env::var("OPENTELEMETRY_AGENT_HOST")
-            .unwrap_or_else(|_| "http://localhost".into()),
-        opentelemetry_agent_port: env::var("OPENTELEMETRY_AGENT_PORT")
-            .unwrap_or_else(|_| "4317".into()),
+        opentelemetry_agent_host,
+        opentelemetry_agent_port,
         service_name: env::var("OPENTELEMETRY_SERVICE_NAME")
             .unwrap_or_else(|_| env!("CARGO_CRATE_NAME").into()),
+        protocol,
+        endpoint,
+        sampler: sampler_from_env(),
     }
 }
 
@@ -184,3 +297,59 @@ pub fn shutdown_opentelemetry_providers(opentelemetry_providers: &OpenTelemetryP
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_sdk::trace::Sampler;
+
+    use super::{OpenTelemetryConfig, OtlpProtocol};
+
+    fn config(protocol: OtlpProtocol, endpoint: Option<&str>) -> OpenTelemetryConfig {
+        OpenTelemetryConfig {
+            opentelemetry_agent_host: String::from("http://localhost"),
+            opentelemetry_agent_port: String::from(protocol.default_port()),
+            service_name: String::from("axum_graphql"),
+            protocol,
+            endpoint: endpoint.map(ToString::to_string),
+            sampler: Sampler::AlwaysOn,
+        }
+    }
+
+    #[test]
+    fn signal_endpoint_shares_endpoint_between_signals() {
+        // arrange
+        let grpc = config(OtlpProtocol::Grpc, Some("http://collector:4317"));
+        let http = config(OtlpProtocol::HttpProtobuf, Some("http://collector:4318/"));
+
+        // act
+        let grpc_outcome = grpc.signal_endpoint("traces");
+        let http_traces_outcome = http.signal_endpoint("traces");
+        let http_logs_outcome = http.signal_endpoint("logs");
+
+        // assert
+        assert_eq!(grpc_outcome.as_deref(), Some("http://collector:4317"));
+        assert_eq!(
+            http_traces_outcome.as_deref(),
+            Some("http://collector:4318/v1/traces")
+        );
+        assert_eq!(
+            http_logs_outcome.as_deref(),
+            Some("http://collector:4318/v1/logs")
+        );
+    }
+
+    #[test]
+    fn signal_endpoint_is_none_without_agent_endpoint() {
+        // arrange
+        let grpc = config(OtlpProtocol::Grpc, None);
+        let http = config(OtlpProtocol::HttpProtobuf, None);
+
+        // act
+        let grpc_outcome = grpc.signal_endpoint("traces");
+        let http_outcome = http.signal_endpoint("metrics");
+
+        // assert
+        assert_eq!(grpc_outcome, None);
+        assert_eq!(http_outcome, None);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, OnceLock},
+    time::Instant,
+};
+
+use async_graphql::{
+    PathSegment, QueryPathNode, QueryPathSegment, Request, Response, ServerError, ServerResult,
+    ValidationResult, Value, Variables,
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery,
+        NextPrepareRequest, NextRequest, NextResolve, NextValidation, ResolveInfo,
+    },
+    parser::types::{DocumentOperations, ExecutableDocument, OperationType},
+    registry::MetaTypeName,
+};
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Histogram},
+};
+use tracing::{Instrument, Level, span};
+
+use super::metrics::{EXPONENTIAL_SECONDS, METER_NAME};
+
+/// Label used for operations sent without a name
+const ANONYMOUS_OPERATION_NAME: &str = "anonymous";
+
+/// Label used for operations sent with a name matching no operation in the document, in place of
+/// the requested name
+const UNKNOWN_OPERATION_NAME: &str = "unknown";
+
+/// Label used for named operations once [`MAX_OPERATION_NAME_LABELS`] distinct names have been
+/// recorded
+const OTHER_OPERATION_NAME: &str = "other";
+
+/// Most distinct operation names used as metric labels.  Operation names are chosen by clients, so
+/// this bounds the number of series each metric has.
+const MAX_OPERATION_NAME_LABELS: usize = 256;
+
+/// Label used for the type of operations which could not be selected from the document, for
+/// example because it did not parse
+const UNKNOWN_OPERATION_TYPE: &str = "unknown";
+
+/// Label used for the path of errors in fields which were not resolved through the extension
+const UNKNOWN_FIELD_PATH: &str = "unknown";
+
+/// Schema extension recording metrics for each GraphQL operation, labelled by operation name and
+/// type, and tracing each stage of execution.  Only the first [`MAX_OPERATION_NAME_LABELS`]
+/// distinct operation names seen are used as labels, with later ones labelled `other`.  Parse, validate, execute and resolver spans are
+/// children of the span current when the schema executes the request, so nest under the
+/// `graphql_execution` span.
+#[derive(Clone)]
+pub struct GraphQLObservability {
+    operations: Counter<u64>,
+    operation_duration: Histogram<f64>,
+    resolver_errors: Counter<u64>,
+    operation_names: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Default for GraphQLObservability {
+    fn default() -> Self {
+        let meter = global::meter(METER_NAME);
+        let operations = meter
+            .u64_counter("graphql_operations")
+            .with_description("Total GraphQL operations")
+            .with_unit("operations")
+            .build();
+        let operation_duration = meter
+            .f64_histogram("graphql_operation_duration_seconds")
+            .with_description("GraphQL operation duration")
+            .with_boundaries(EXPONENTIAL_SECONDS.to_vec())
+            .build();
+        let resolver_errors = meter
+            .u64_counter("graphql_resolver_errors")
+            .with_description("Total errors returned by GraphQL field resolvers")
+            .with_unit("errors")
+            .build();
+
+        Self {
+            operations,
+            operation_duration,
+            resolver_errors,
+            operation_names: Arc::default(),
+        }
+    }
+}
+
+impl ExtensionFactory for GraphQLObservability {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLObservabilityExtension {
+            instruments: self.clone(),
+            requested_operation_name: OnceLock::new(),
+            operation: OnceLock::new(),
+            field_paths: Mutex::default(),
+        })
+    }
+}
+
+/// Operation selected from a parsed document for execution
+#[derive(Debug, PartialEq, Eq)]
+struct Operation {
+    name: Option<String>,
+    ty: OperationType,
+}
+
+/// Per-request state for [`GraphQLObservability`], remembering the requested operation name, the
+/// operation selected once the document is parsed, and the field names behind each response path,
+/// for labelling metrics
+struct GraphQLObservabilityExtension {
+    instruments: GraphQLObservability,
+    requested_operation_name: OnceLock<Option<String>>,
+    operation: OnceLock<Operation>,
+    field_paths: Mutex<HashMap<String, String>>,
+}
+
+impl GraphQLObservabilityExtension {
+    /// Name of the selected operation.  Names which were requested, but match no operation, are
+    /// replaced with `unknown`.
+    fn operation_name(&self) -> String {
+        match (
+            self.operation.get(),
+            self.requested_operation_name.get().cloned().flatten(),
+        ) {
+            (Some(operation), _) => operation
+                .name
+                .clone()
+                .unwrap_or_else(|| ANONYMOUS_OPERATION_NAME.to_string()),
+            (None, Some(_)) => UNKNOWN_OPERATION_NAME.to_string(),
+            (None, None) => ANONYMOUS_OPERATION_NAME.to_string(),
+        }
+    }
+
+    /// Operation name metrics are labelled with, falling back to `other` once the limit on
+    /// distinct names is reached
+    fn operation_label(&self) -> String {
+        let operation_name = self.operation_name();
+        if self
+            .operation
+            .get()
+            .is_none_or(|operation| operation.name.is_none())
+        {
+            return operation_name;
+        }
+
+        capped_label(
+            &self.instruments.operation_names,
+            operation_name,
+            MAX_OPERATION_NAME_LABELS,
+        )
+    }
+
+    fn operation_type(&self) -> String {
+        self.operation.get().map_or_else(
+            || UNKNOWN_OPERATION_TYPE.to_string(),
+            |operation| operation.ty.to_string(),
+        )
+    }
+
+    /// Remember the field names leading to the field at `path_node`, named `name`, keyed by its
+    /// response path, so errors are labelled by field names, rather than client-chosen aliases.
+    /// The parent field, if any, must already be recorded.
+    fn record_field_path(&self, path_node: &QueryPathNode<'_>, name: &str) {
+        let mut field_paths = self
+            .field_paths
+            .lock()
+            .expect("field paths lock should not be poisoned");
+        let parent_path = path_node
+            .parents()
+            .find(|node| matches!(node.segment, QueryPathSegment::Name(_)))
+            .and_then(|node| field_paths.get(&response_path(node)));
+        let field_path = match parent_path {
+            Some(parent_path) => format!("{parent_path}.{name}"),
+            None => name.to_string(),
+        };
+        field_paths.insert(response_path(path_node), field_path);
+    }
+
+    /// Path of the field `error` relates to, as field names joined with `.`
+    fn error_field_path(&self, error: &ServerError) -> String {
+        self.field_paths
+            .lock()
+            .expect("field paths lock should not be poisoned")
+            .get(&error_path(error))
+            .cloned()
+            .unwrap_or_else(|| UNKNOWN_FIELD_PATH.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLObservabilityExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let start = Instant::now();
+
+        let response = next.run(ctx).await;
+
+        let latency = start.elapsed().as_secs_f64();
+        let operation_name = self.operation_label();
+        let attributes = [
+            KeyValue::new("operation_name", operation_name.clone()),
+            KeyValue::new("operation_type", self.operation_type()),
+        ];
+        self.instruments.operations.add(1, &attributes);
+        self.instruments
+            .operation_duration
+            .record(latency, &attributes);
+
+        // errors without a path are request errors, such as parse or validation failures
+        for error in response
+            .errors
+            .iter()
+            .filter(|error| !error.path.is_empty())
+        {
+            self.instruments.resolver_errors.add(
+                1,
+                &[
+                    KeyValue::new("operation_name", operation_name.clone()),
+                    KeyValue::new("path", self.error_field_path(error)),
+                ],
+            );
+        }
+
+        response
+    }
+
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let _ = self
+            .requested_operation_name
+            .set(request.operation_name.clone());
+
+        next.run(ctx, request).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let span = span!(Level::INFO, "parse");
+        let document = next.run(ctx, query, variables).instrument(span).await?;
+
+        let requested_operation_name = self.requested_operation_name.get().cloned().flatten();
+        if let Some(operation) = selected_operation(&document, requested_operation_name.as_deref())
+        {
+            let _ = self.operation.set(operation);
+        }
+
+        Ok(document)
+    }
+
+    async fn validation(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        next: NextValidation<'_>,
+    ) -> Result<ValidationResult, Vec<ServerError>> {
+        let span = span!(Level::INFO, "validate");
+        next.run(ctx).instrument(span).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let span = span!(
+            Level::INFO,
+            "execute",
+            graphql.operation.name = %self.operation_name(),
+            graphql.operation.type = %self.operation_type(),
+        );
+        next.run(ctx, operation_name).instrument(span).await
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        // skip scalar and enum fields below the root, most of which are read straight from their
+        // parent object, to avoid creating a span for every value in the response
+        let is_leaf = ctx
+            .schema_env
+            .registry
+            .types
+            .get(MetaTypeName::concrete_typename(info.return_type))
+            .is_some_and(async_graphql::registry::MetaType::is_leaf);
+        let is_root = info.path_node.parent.is_none();
+        if info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+        if is_leaf && !is_root {
+            // only look up the field names of leaf fields which fail
+            let (path_node, name) = (info.path_node, info.name);
+            return next.run(ctx, info).await.inspect_err(|_| {
+                self.record_field_path(path_node, name);
+            });
+        }
+
+        self.record_field_path(info.path_node, info.name);
+        let span = span!(
+            Level::INFO,
+            "resolve",
+            graphql.field.path = %info.path_node,
+            graphql.field.parent_type = info.parent_type,
+            graphql.field.type = info.return_type,
+        );
+        next.run(ctx, info).instrument(span).await
+    }
+}
+
+/// Returns `label` if it is one of the `limit` labels in `labels`, or there is room to add it,
+/// otherwise `other`
+fn capped_label(labels: &Mutex<HashSet<String>>, label: String, limit: usize) -> String {
+    let mut labels = labels
+        .lock()
+        .expect("operation names lock should not be poisoned");
+    if labels.contains(&label) {
+        return label;
+    }
+    if labels.len() >= limit {
+        return OTHER_OPERATION_NAME.to_string();
+    }
+    labels.insert(label.clone());
+
+    label
+}
+
+/// Operation in `document` which will be executed for a request with `operation_name`, following
+/// the same rules as the schema.  Returns `None` if there is no such operation.
+fn selected_operation(
+    document: &ExecutableDocument,
+    operation_name: Option<&str>,
+) -> Option<Operation> {
+    match (&document.operations, operation_name) {
+        (DocumentOperations::Single(operation), None) => Some(Operation {
+            name: None,
+            ty: operation.node.ty,
+        }),
+        (DocumentOperations::Multiple(operations), Some(name)) => {
+            operations.get(name).map(|operation| Operation {
+                name: Some(name.to_string()),
+                ty: operation.node.ty,
+            })
+        }
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            operations.iter().next().map(|(name, operation)| Operation {
+                name: Some(name.to_string()),
+                ty: operation.node.ty,
+            })
+        }
+        (DocumentOperations::Single(_), Some(_)) | (DocumentOperations::Multiple(_), None) => None,
+    }
+}
+
+/// Path of the field `error` relates to, as response keys joined with `.`.  List indices are left
+/// out, so errors in each item of a list are counted together.
+fn error_path(error: &ServerError) -> String {
+    error
+        .path
+        .iter()
+        .filter_map(|segment| match segment {
+            PathSegment::Field(name) => Some(name.as_str()),
+            PathSegment::Index(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Path of the field at `path_node`, in the same form as [`error_path`]
+fn response_path(path_node: &QueryPathNode<'_>) -> String {
+    let mut keys = Vec::new();
+    for node in std::iter::once(path_node).chain(path_node.parents()) {
+        if let QueryPathSegment::Name(key) = node.segment {
+            keys.push(key);
+        }
+    }
+    keys.reverse();
+
+    keys.join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{PathSegment, ServerError, parser::parse_query};
+
+    use std::{collections::HashSet, sync::Mutex};
+
+    use super::{Operation, OperationType, capped_label, error_path, selected_operation};
+
+    #[test]
+    fn selected_operation_follows_operation_name() {
+        // arrange
+        let anonymous = parse_query("{ hello }").unwrap();
+        let named = parse_query("mutation CreateDraft { hello }").unwrap();
+        let multiple = parse_query("query Hello { hello } mutation CreateDraft { hello }").unwrap();
+
+        // act
+        let anonymous_outcome = selected_operation(&anonymous, None);
+        let named_outcome = selected_operation(&named, None);
+        let multiple_outcome = selected_operation(&multiple, Some("Hello"));
+        let ambiguous_outcome = selected_operation(&multiple, None);
+        let unknown_outcome = selected_operation(&multiple, Some("Unknown"));
+
+        // assert
+        assert_eq!(
+            anonymous_outcome,
+            Some(Operation {
+                name: None,
+                ty: OperationType::Query
+            })
+        );
+        assert_eq!(
+            named_outcome,
+            Some(Operation {
+                name: Some("CreateDraft".to_string()),
+                ty: OperationType::Mutation
+            })
+        );
+        assert_eq!(
+            multiple_outcome,
+            Some(Operation {
+                name: Some("Hello".to_string()),
+                ty: OperationType::Query
+            })
+        );
+        assert_eq!(ambiguous_outcome, None);
+        assert_eq!(unknown_outcome, None);
+    }
+
+    #[test]
+    fn capped_label_falls_back_to_other_once_limit_is_reached() {
+        // arrange
+        let labels = Mutex::new(HashSet::new());
+
+        // act
+        let outcomes = ["First", "Second", "Third", "First"]
+            .map(|label| capped_label(&labels, label.to_string(), 2));
+
+        // assert
+        assert_eq!(outcomes, ["First", "Second", "other", "First"]);
+    }
+
+    #[test]
+    fn error_path_leaves_out_list_indices() {
+        // arrange
+        let mut error = ServerError::new("Failed", None);
+        error.path = vec![
+            PathSegment::Field("posts".to_string()),
+            PathSegment::Field("edges".to_string()),
+            PathSegment::Index(2),
+            PathSegment::Field("node".to_string()),
+            PathSegment::Field("author".to_string()),
+        ];
+
+        // act
+        let outcome = error_path(&error);
+
+        // assert
+        assert_eq!(outcome, "posts.edges.node.author");
+    }
+}
@@ -1,19 +1,29 @@
-use opentelemetry_otlp::LogExporter;
+use opentelemetry_otlp::{LogExporter, Protocol, WithExportConfig};
 use opentelemetry_sdk::logs::SdkLoggerProvider;
 
-use super::common::{OpenTelemetryConfig, get_resource};
+use super::common::{OpenTelemetryConfig, OtlpProtocol, get_resource};
 
 /// Create OpenTelemetry logging provider
 ///
 /// # Panics
 ///
-/// Panics if tokio runtime is not available.
+/// Panics if the OTLP log exporter cannot be built, for example outside a tokio runtime.
 #[must_use]
 pub fn init_logs(config: &OpenTelemetryConfig) -> SdkLoggerProvider {
-    let exporter = LogExporter::builder()
-        .with_tonic()
-        .build()
-        .expect("should be using a tokio runtime");
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => config
+            .with_signal_endpoint(LogExporter::builder().with_tonic(), "logs")
+            .build(),
+        OtlpProtocol::HttpProtobuf => config
+            .with_signal_endpoint(
+                LogExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary),
+                "logs",
+            )
+            .build(),
+    }
+    .unwrap_or_else(|error| panic!("should be able to build the OTLP log exporter: {error}"));
 
     SdkLoggerProvider::builder()
         .with_resource(get_resource(config))
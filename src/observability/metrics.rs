@@ -1,24 +1,98 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    env,
+    net::SocketAddr,
+    sync::{Arc, LazyLock, OnceLock},
+    time::Instant,
+};
 
 use crate::router::AppState;
 
-use super::{common::OpenTelemetryConfig, get_resource};
+use super::{
+    common::{OpenTelemetryConfig, OtlpProtocol},
+    get_resource,
+};
 use axum::{
     debug_middleware,
     extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
     middleware::Next,
     response::IntoResponse,
 };
-use opentelemetry::{KeyValue, global};
-use opentelemetry_otlp::MetricExporter;
-use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Counter, Gauge, Histogram, Meter},
+};
+use opentelemetry_otlp::{MetricExporter, Protocol, WithExportConfig};
+use opentelemetry_sdk::metrics::{MeterProviderBuilder, SdkMeterProvider};
+use prometheus::{Encoder, Registry, TextEncoder};
+
+/// Name of the meter app metrics are recorded with
+pub(super) const METER_NAME: &str = "axum-graphql";
 
 const REQUEST_DURATION_METRIC_NAME: &str = "http_requests_duration_seconds";
 
-const EXPONENTIAL_SECONDS: &[f64] = &[
+pub(super) const EXPONENTIAL_SECONDS: &[f64] = &[
     0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
 ];
 
+/// Database instruments, created once a meter provider is set, since queries only have access to
+/// the database pool, rather than to app state
+static DATABASE_METRICS: OnceLock<DatabaseMetrics> = OnceLock::new();
+
+/// Registry the Prometheus exporter writes to, and the `/metrics` endpoint reads from
+static PROMETHEUS_REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+/// Where the Prometheus `/metrics` scrape endpoint is served
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MetricsEndpoint {
+    /// No scrape endpoint
+    #[default]
+    Disabled,
+
+    /// `/metrics` on the main app router
+    MainRouter,
+
+    /// `/metrics` on a separate admin listener, bound to this address, so the endpoint need not be
+    /// exposed alongside the API
+    AdminListener(SocketAddr),
+}
+
+impl MetricsEndpoint {
+    /// Serve the endpoint if `PROMETHEUS_ENABLED` is `true`, on a separate listener bound to
+    /// `PROMETHEUS_LISTEN_ADDRESS`, if set, or otherwise on the main router.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `PROMETHEUS_ENABLED` exists and is not either `true` or `false`, or if
+    /// `PROMETHEUS_LISTEN_ADDRESS` exists, but is not a socket address.
+    #[must_use]
+    pub fn from_env() -> Self {
+        if !prometheus_enabled() {
+            return Self::Disabled;
+        }
+
+        env::var("PROMETHEUS_LISTEN_ADDRESS").map_or(Self::MainRouter, |value| {
+            Self::AdminListener(value.parse().expect(
+                "`PROMETHEUS_LISTEN_ADDRESS` env variable should be a socket address, such as \
+                 `127.0.0.1:9090`",
+            ))
+        })
+    }
+}
+
+/// Returns `true` if the `PROMETHEUS_ENABLED` env variable is set to `true`
+///
+/// # Panics
+///
+/// Panics if `PROMETHEUS_ENABLED` exists and is not either `true` or `false`.
+#[must_use]
+pub fn prometheus_enabled() -> bool {
+    env::var("PROMETHEUS_ENABLED")
+        .unwrap_or_else(|_| "false".into())
+        .parse()
+        .expect("`PROMETHEUS_ENABLED` env variable should be either `true` or `false`")
+}
+
 #[derive(Clone)]
 pub struct AppMetricsState {
     pub meter: opentelemetry::metrics::Meter,
@@ -28,7 +102,7 @@ pub struct AppMetricsState {
 
 impl Default for AppMetricsState {
     fn default() -> Self {
-        let meter = global::meter("axum-graphql");
+        let meter = global::meter(METER_NAME);
         let counter = meter
             .u64_counter("http_requests_total")
             .with_description("Total HTTP requests")
@@ -48,6 +122,71 @@ impl Default for AppMetricsState {
     }
 }
 
+/// Instruments recording database query durations and errors, and connection pool statistics,
+/// created with the same meter as [`AppMetricsState`]
+pub struct DatabaseMetrics {
+    /// Query duration, labelled by query name and SQL operation
+    pub query_duration: Histogram<f64>,
+
+    /// Failed queries, labelled by query name and error type
+    pub query_errors: Counter<u64>,
+
+    /// Open connections in the pool, whether idle or in use
+    pub pool_size: Gauge<u64>,
+
+    /// Idle connections in the pool
+    pub pool_idle_connections: Gauge<u64>,
+
+    /// Time taken to acquire a connection from the pool for the latest query
+    pub pool_acquire_wait: Gauge<f64>,
+}
+
+impl DatabaseMetrics {
+    fn new(meter: &Meter) -> Self {
+        let query_duration = meter
+            .f64_histogram("db_query_duration_seconds")
+            .with_description("database query duration")
+            .with_boundaries(EXPONENTIAL_SECONDS.to_vec())
+            .build();
+        let query_errors = meter
+            .u64_counter("db_query_errors")
+            .with_description("Total failed database queries")
+            .with_unit("errors")
+            .build();
+        let pool_size = meter
+            .u64_gauge("db_pool_size")
+            .with_description("Open database pool connections, idle or in use")
+            .build();
+        let pool_idle_connections = meter
+            .u64_gauge("db_pool_idle_connections")
+            .with_description("Idle database pool connections")
+            .build();
+        let pool_acquire_wait = meter
+            .f64_gauge("db_pool_acquire_wait_seconds")
+            .with_description("Time taken to acquire a database pool connection")
+            .build();
+
+        Self {
+            query_duration,
+            query_errors,
+            pool_size,
+            pool_idle_connections,
+            pool_acquire_wait,
+        }
+    }
+
+    /// Returns the database instruments, or `None` if no meter provider has been set up, so
+    /// metrics are not being recorded
+    pub fn get() -> Option<&'static Self> {
+        DATABASE_METRICS.get()
+    }
+
+    /// Create the database instruments with the global meter provider, which should already be set
+    fn init() {
+        DATABASE_METRICS.get_or_init(|| Self::new(&global::meter(METER_NAME)));
+    }
+}
+
 #[debug_middleware]
 pub async fn track(
     State(state): State<Arc<AppState>>,
@@ -79,13 +218,52 @@ pub async fn track(
     response
 }
 
-fn init_provider(config: &OpenTelemetryConfig) -> SdkMeterProvider {
-    let exporter = MetricExporter::builder()
-        .with_tonic()
+/// Serves metrics recorded by the app in the Prometheus text exposition format.  Metrics are only
+/// recorded once [`init_prometheus_metrics`] or [`init_metrics`] has run with Prometheus enabled.
+pub async fn metrics_handler() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+
+    match encoder.encode(&PROMETHEUS_REGISTRY.gather(), &mut body) {
+        Ok(()) => ([(header::CONTENT_TYPE, encoder.format_type())], body).into_response(),
+        Err(error) => {
+            tracing::error!("Failed to encode Prometheus metrics: {error}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Add a reader, exporting to [`PROMETHEUS_REGISTRY`], to `builder` if Prometheus is enabled
+fn with_prometheus_reader(builder: MeterProviderBuilder) -> MeterProviderBuilder {
+    if !prometheus_enabled() {
+        return builder;
+    }
+
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_registry(PROMETHEUS_REGISTRY.clone())
         .build()
-        .expect("Failed to create metric exporter");
+        .expect("Failed to create Prometheus exporter");
+
+    builder.with_reader(exporter)
+}
+
+fn init_provider(config: &OpenTelemetryConfig) -> SdkMeterProvider {
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => config
+            .with_signal_endpoint(MetricExporter::builder().with_tonic(), "metrics")
+            .build(),
+        OtlpProtocol::HttpProtobuf => config
+            .with_signal_endpoint(
+                MetricExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary),
+                "metrics",
+            )
+            .build(),
+    }
+    .unwrap_or_else(|error| panic!("should be able to build the OTLP metric exporter: {error}"));
 
-    SdkMeterProvider::builder()
+    with_prometheus_reader(SdkMeterProvider::builder())
         .with_periodic_exporter(exporter)
         .with_resource(get_resource(config))
         .build()
@@ -96,6 +274,25 @@ pub fn init_metrics(config: &OpenTelemetryConfig) -> SdkMeterProvider {
     let meter_provider = init_provider(config);
 
     global::set_meter_provider(meter_provider.clone());
+    DatabaseMetrics::init();
 
     meter_provider
 }
+
+/// Record metrics for the Prometheus scrape endpoint only, for when OpenTelemetry is not enabled.
+/// Returns `None`, recording nothing, unless Prometheus is enabled.
+#[must_use]
+pub fn init_prometheus_metrics(config: &OpenTelemetryConfig) -> Option<SdkMeterProvider> {
+    if !prometheus_enabled() {
+        return None;
+    }
+
+    let meter_provider = with_prometheus_reader(SdkMeterProvider::builder())
+        .with_resource(get_resource(config))
+        .build();
+
+    global::set_meter_provider(meter_provider.clone());
+    DatabaseMetrics::init();
+
+    Some(meter_provider)
+}
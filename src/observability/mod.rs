@@ -1,4 +1,5 @@
 pub mod common;
+pub mod graphql;
 pub mod logging;
 pub mod metrics;
 pub mod tracing;
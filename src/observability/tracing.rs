@@ -1,34 +1,95 @@
-use opentelemetry::{global, trace::TracerProvider};
+use std::env;
+
+use axum::http::HeaderMap;
+use opentelemetry::{
+    Context, global, propagation::TextMapCompositePropagator, trace::TracerProvider,
+};
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
-use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
 use opentelemetry_sdk::{
     logs::SdkLoggerProvider,
+    propagation::{BaggagePropagator, TraceContextPropagator},
     trace::{RandomIdGenerator, Sampler, SdkTracerProvider},
 };
 use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
-use super::{common::OpenTelemetryConfig, create_format_filter, create_otel_filter, get_resource};
+use super::{
+    common::{OpenTelemetryConfig, OtlpProtocol},
+    create_format_filter, create_otel_filter, get_resource,
+};
 
 fn init_provider(config: &OpenTelemetryConfig) -> SdkTracerProvider {
-    let exporter = SpanExporter::builder()
-        .with_tonic()
-        .with_endpoint(format!(
-            "{}:{}",
-            config.opentelemetry_agent_host, config.opentelemetry_agent_port
-        ))
-        .with_timeout(std::time::Duration::from_secs(3))
-        .build()
-        .expect("should be using a tokio runtime");
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => config
+            .with_signal_endpoint(SpanExporter::builder().with_tonic(), "traces")
+            .build(),
+        OtlpProtocol::HttpProtobuf => config
+            .with_signal_endpoint(
+                SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary),
+                "traces",
+            )
+            .build(),
+    }
+    .unwrap_or_else(|error| panic!("should be able to build the OTLP span exporter: {error}"));
 
     SdkTracerProvider::builder()
-        .with_sampler(Sampler::AlwaysOn)
+        .with_sampler(config.sampler.clone())
         .with_id_generator(RandomIdGenerator::default())
         .with_resource(get_resource(config))
         .with_batch_exporter(exporter)
         .build()
 }
 
+/// Read the trace sampler from the standard `OTEL_TRACES_SAMPLER` and `OTEL_TRACES_SAMPLER_ARG`
+/// env variables, defaulting to `parentbased_always_on`, so traces continued from a caller follow
+/// the caller's sampling decision
+///
+/// # Panics
+///
+/// Panics if `OTEL_TRACES_SAMPLER` exists, but is not a known sampler, or if
+/// `OTEL_TRACES_SAMPLER_ARG` exists, but is not a ratio between 0 and 1.
+#[must_use]
+pub fn sampler_from_env() -> Sampler {
+    let ratio = env::var("OTEL_TRACES_SAMPLER_ARG").map_or(1.0, |value| {
+        value
+            .parse()
+            .ok()
+            .filter(|ratio| (0.0..=1.0).contains(ratio))
+            .expect("`OTEL_TRACES_SAMPLER_ARG` env variable should be a ratio between 0 and 1")
+    });
+    let name = env::var("OTEL_TRACES_SAMPLER").unwrap_or_else(|_| "parentbased_always_on".into());
+
+    parse_sampler(&name, ratio).unwrap_or_else(|| {
+        panic!(
+            "`OTEL_TRACES_SAMPLER` env variable should be one of `always_on`, `always_off`, \
+             `traceidratio`, `parentbased_always_on`, `parentbased_always_off` or \
+             `parentbased_traceidratio`, not `{name}`"
+        )
+    })
+}
+
+/// Sampler named `name`, using the OpenTelemetry specification names, where `ratio` is the
+/// fraction of traces the ratio-based samplers record.  Returns `None` for unknown names.
+fn parse_sampler(name: &str, ratio: f64) -> Option<Sampler> {
+    let sampler = match name {
+        "always_on" => Sampler::AlwaysOn,
+        "always_off" => Sampler::AlwaysOff,
+        "traceidratio" => Sampler::TraceIdRatioBased(ratio),
+        "parentbased_always_on" => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        "parentbased_always_off" => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        "parentbased_traceidratio" => {
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio)))
+        }
+        _ => return None,
+    };
+
+    Some(sampler)
+}
+
 #[must_use]
 pub fn init_tracing(
     config: &OpenTelemetryConfig,
@@ -53,3 +114,47 @@ pub fn init_tracing(
 
     tracer_provider
 }
+
+/// Set the global propagator to read, and write, trace context and baggage as W3C `traceparent`,
+/// `tracestate` and `baggage` headers
+pub fn init_propagator() {
+    global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ]));
+}
+
+/// Trace context and baggage sent by the caller in request `headers`, using the global propagator.
+/// Returns an empty context if the headers carry no valid trace context.
+pub(crate) fn remote_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_sampler;
+
+    #[test]
+    fn parse_sampler_reads_specification_sampler_names() {
+        // arrange
+        let ratio = 0.25;
+
+        // act
+        let ratio_outcome = parse_sampler("traceidratio", ratio);
+        let parent_based_outcome = parse_sampler("parentbased_traceidratio", ratio);
+        let always_off_outcome = parse_sampler("always_off", ratio);
+        let unknown_outcome = parse_sampler("sometimes", ratio);
+
+        // assert
+        assert_eq!(
+            format!("{ratio_outcome:?}"),
+            "Some(TraceIdRatioBased(0.25))"
+        );
+        assert_eq!(
+            format!("{parent_based_outcome:?}"),
+            "Some(ParentBased(TraceIdRatioBased(0.25)))"
+        );
+        assert_eq!(format!("{always_off_outcome:?}"), "Some(AlwaysOff)");
+        assert!(unknown_outcome.is_none());
+    }
+}
@@ -8,9 +8,16 @@ use tower::{ServiceBuilder, timeout::TimeoutLayer};
 use tower_http::{compression::CompressionLayer, services::ServeDir};
 
 use crate::{
-    model::ServiceSchema,
-    observability::metrics::{self, AppMetricsState},
-    routes::{graphql_handler, graphql_playground, health},
+    auth::Authenticator,
+    model::{
+        ServiceSchema,
+        cover_image::{COVER_IMAGE_PATH, CoverImageStorage},
+    },
+    observability::metrics::{self, AppMetricsState, MetricsEndpoint, metrics_handler},
+    routes::{
+        graphql_get_handler, graphql_handler, health, persisted_queries::PersistedQueries,
+        subscription::graphql_ws_handler,
+    },
 };
 
 #[derive(Clone)]
@@ -18,20 +25,38 @@ pub struct AppState {
     pub metrics: AppMetricsState,
 }
 
-pub(crate) fn init_router(schema: ServiceSchema) -> Router {
+pub(crate) fn init_router(
+    schema: ServiceSchema,
+    authenticator: Authenticator,
+    persisted_queries: PersistedQueries,
+    cover_images: &CoverImageStorage,
+    metrics_endpoint: MetricsEndpoint,
+) -> Router {
     let state = AppState {
         metrics: AppMetricsState::default(),
     };
     let shared_state = Arc::new(state);
 
-    Router::new()
-        .route("/", get(graphql_playground).post(graphql_handler))
+    let router = if metrics_endpoint == MetricsEndpoint::MainRouter {
+        metrics_router()
+    } else {
+        Router::new()
+    };
+
+    router
+        .route("/", get(graphql_get_handler).post(graphql_handler))
+        .route("/ws", get(graphql_ws_handler))
         .route("/health", get(health))
         // serve GraphQL Playground CDN assets locally
         .nest_service("/assets", ServeDir::new("public"))
+        // serve uploaded cover images
+        .nest_service(COVER_IMAGE_PATH, ServeDir::new(&cover_images.directory))
         .layer(
             ServiceBuilder::new()
                 .layer(Extension(schema))
+                .layer(Extension(authenticator))
+                .layer(Extension(persisted_queries))
+                .layer(Extension(cover_images.multipart_options()))
                 .layer(CompressionLayer::new())
                 .layer(HandleErrorLayer::new(|_: BoxError| async {
                     StatusCode::REQUEST_TIMEOUT
@@ -44,3 +69,11 @@ pub(crate) fn init_router(schema: ServiceSchema) -> Router {
         )
         .with_state(shared_state)
 }
+
+/// Router serving the Prometheus scrape endpoint on `/metrics`
+pub(crate) fn metrics_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/metrics", get(metrics_handler))
+}
@@ -0,0 +1,164 @@
+use axum::http::{HeaderMap, StatusCode, header};
+
+/// Media types a GraphQL response may be sent as, following the GraphQL-over-HTTP specification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ResponseMediaType {
+    /// `application/graphql-response+json`, where requests which fail before execution get a
+    /// `4xx` status code
+    GraphQLResponseJson,
+
+    /// `application/json`, for legacy clients, where GraphQL errors always get a `200 OK` status
+    /// code
+    Json,
+}
+
+impl ResponseMediaType {
+    const GRAPHQL_RESPONSE_JSON: &str = "application/graphql-response+json";
+    const JSON: &str = "application/json";
+
+    /// Pick the response media type the client prefers, from the `Accept` header in `headers`.
+    /// Clients which send no `Accept` header get `application/json`.  Returns `None` when the
+    /// client accepts neither supported media type.
+    pub(crate) fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Some(Self::Json);
+        };
+
+        let ranges = accept.split(',').filter_map(parse_media_range);
+        let mut graphql_response_json_quality = 0.0_f32;
+        let mut json_quality = 0.0_f32;
+        for (range, quality) in ranges {
+            match range.as_str() {
+                Self::GRAPHQL_RESPONSE_JSON => {
+                    graphql_response_json_quality = graphql_response_json_quality.max(quality);
+                }
+                Self::JSON => json_quality = json_quality.max(quality),
+                "application/*" | "*/*" => {
+                    graphql_response_json_quality = graphql_response_json_quality.max(quality);
+                    json_quality = json_quality.max(quality);
+                }
+                _ => {}
+            }
+        }
+
+        if graphql_response_json_quality > 0.0 && graphql_response_json_quality >= json_quality {
+            Some(Self::GraphQLResponseJson)
+        } else if json_quality > 0.0 {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+
+    /// Value for the `Content-Type` response header
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Self::GraphQLResponseJson => Self::GRAPHQL_RESPONSE_JSON,
+            Self::Json => Self::JSON,
+        }
+    }
+
+    /// Status code for a request which failed before execution, for example, because it did not
+    /// parse or validate
+    pub(crate) fn request_error_status(self) -> StatusCode {
+        match self {
+            Self::GraphQLResponseJson => StatusCode::BAD_REQUEST,
+            Self::Json => StatusCode::OK,
+        }
+    }
+}
+
+/// Returns `true` if the `Accept` header in `headers` lists `text/html`, as browsers do when
+/// navigating to a page
+pub(crate) fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .filter_map(parse_media_range)
+                .any(|(range, quality)| range == "text/html" && quality > 0.0)
+        })
+}
+
+/// Split a single `Accept` header entry into its lowercase media range and quality value, which
+/// defaults to 1
+fn parse_media_range(entry: &str) -> Option<(String, f32)> {
+    let mut parts = entry.split(';').map(str::trim);
+    let range = parts.next().filter(|value| !value.is_empty())?;
+    let quality = parts
+        .filter_map(|parameter| parameter.strip_prefix("q="))
+        .find_map(|value| value.parse().ok())
+        .unwrap_or(1.0);
+
+    Some((range.to_ascii_lowercase(), quality))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderMap, HeaderValue, header};
+
+    use super::{ResponseMediaType, accepts_html};
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+
+        headers
+    }
+
+    #[test]
+    fn negotiate_prefers_graphql_response_json_unless_json_has_higher_quality() {
+        // arrange
+        let both = accept("application/json, application/graphql-response+json");
+        let json_preferred = accept("application/graphql-response+json;q=0.5, application/json");
+        let wildcard = accept("*/*");
+
+        // act
+        let both_outcome = ResponseMediaType::negotiate(&both);
+        let json_preferred_outcome = ResponseMediaType::negotiate(&json_preferred);
+        let wildcard_outcome = ResponseMediaType::negotiate(&wildcard);
+
+        // assert
+        assert_eq!(both_outcome, Some(ResponseMediaType::GraphQLResponseJson));
+        assert_eq!(json_preferred_outcome, Some(ResponseMediaType::Json));
+        assert_eq!(
+            wildcard_outcome,
+            Some(ResponseMediaType::GraphQLResponseJson)
+        );
+    }
+
+    #[test]
+    fn negotiate_defaults_to_json_and_rejects_unsupported_types() {
+        // arrange
+        let missing = HeaderMap::new();
+        let unsupported = accept("text/plain, application/json;q=0");
+
+        // act
+        let missing_outcome = ResponseMediaType::negotiate(&missing);
+        let unsupported_outcome = ResponseMediaType::negotiate(&unsupported);
+
+        // assert
+        assert_eq!(missing_outcome, Some(ResponseMediaType::Json));
+        assert_eq!(unsupported_outcome, None);
+    }
+
+    #[test]
+    fn accepts_html_matches_browser_navigation() {
+        // arrange
+        let browser = accept("text/html,application/xhtml+xml,*/*;q=0.8");
+        let client = accept("application/json");
+
+        // act
+        let browser_outcome = accepts_html(&browser);
+        let client_outcome = accepts_html(&client);
+
+        // assert
+        assert!(browser_outcome);
+        assert!(!client_outcome);
+    }
+}
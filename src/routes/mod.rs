@@ -1,12 +1,35 @@
-use async_graphql::http::{GraphQLPlaygroundConfig, playground_source};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::{Json, extract::Extension, http::StatusCode, response::Html};
-use opentelemetry::trace::TraceContextExt;
-use serde::Serialize;
+mod media_type;
+pub mod persisted_queries;
+pub(crate) mod subscription;
+
+use async_graphql::{
+    CacheControl, ErrorExtensions, ParseRequestError, ServerError, Variables,
+    futures_util::TryStreamExt,
+    http::{GraphQLPlaygroundConfig, MultipartOptions, playground_source, receive_body},
+    parser::{
+        parse_query,
+        types::{DocumentOperations, OperationType},
+    },
+};
+use async_graphql_axum::{GraphQLResponse, rejection::GraphQLRejection};
+use axum::{
+    Json,
+    extract::{Extension, Query},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{Html, IntoResponse, Response},
+};
+use opentelemetry::trace::{FutureExt, TraceContextExt, TraceId};
+use serde::{Deserialize, Serialize};
 use tracing::{Instrument, Level, span};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-use crate::model::ServiceSchema;
+use crate::{
+    auth::{Authenticator, Viewer},
+    model::ServiceSchema,
+    observability::tracing::remote_context,
+};
+use media_type::{ResponseMediaType, accepts_html};
+use persisted_queries::PersistedQueries;
 
 #[derive(Serialize)]
 pub(crate) struct Health {
@@ -19,7 +42,7 @@ pub(crate) async fn health() -> (StatusCode, Json<Health>) {
     (StatusCode::OK, Json(health))
 }
 
-pub(crate) async fn graphql_playground() -> Html<String> {
+pub(crate) fn graphql_playground() -> Html<String> {
     Html(
         // serve GraphQL Playground CDN assets locally
         playground_source(GraphQLPlaygroundConfig::new("/").subscription_endpoint("/ws"))
@@ -34,27 +57,339 @@ pub(crate) async fn graphql_playground() -> Html<String> {
     )
 }
 
+/// Query string parameters of a GraphQL request sent with GET, where `variables` and
+/// `extensions` hold JSON
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GraphQLGetParams {
+    query: Option<String>,
+    operation_name: Option<String>,
+    variables: Option<String>,
+    extensions: Option<String>,
+}
+
+/// Serves queries sent with GET, following the GraphQL-over-HTTP specification, so CDNs can cache
+/// their responses.  Browsers navigating to the endpoint, which ask for `text/html` and send no
+/// query, get GraphQL Playground instead.
+pub(crate) async fn graphql_get_handler(
+    Extension(schema): Extension<ServiceSchema>,
+    Extension(authenticator): Extension<Authenticator>,
+    Extension(persisted_queries): Extension<PersistedQueries>,
+    headers: HeaderMap,
+    Query(params): Query<GraphQLGetParams>,
+) -> Response {
+    let GraphQLGetParams {
+        query,
+        operation_name,
+        variables,
+        extensions,
+    } = params;
+    if query.is_none() && extensions.is_none() && accepts_html(&headers) {
+        return graphql_playground().into_response();
+    }
+    let Some(media_type) = ResponseMediaType::negotiate(&headers) else {
+        return not_acceptable();
+    };
+
+    let variables = match variables.as_deref().map(serde_json::from_str).transpose() {
+        Ok(value) => value.map(Variables::from_json).unwrap_or_default(),
+        Err(_) => return bad_request(media_type, "`variables` should be a JSON object"),
+    };
+    let extensions = match extensions.as_deref().map(serde_json::from_str).transpose() {
+        Ok(value) => value.unwrap_or_default(),
+        Err(_) => return bad_request(media_type, "`extensions` should be a JSON object"),
+    };
+    let mut request = async_graphql::Request::new(query.unwrap_or_default()).variables(variables);
+    request.operation_name = operation_name;
+    request.extensions = extensions;
+    if request.query.is_empty() && !PersistedQueries::is_persisted_query(&request) {
+        return bad_request(media_type, "`query` parameter is required");
+    }
+
+    let request = match persisted_queries.resolve(request) {
+        Ok(value) => value,
+        Err(error) => {
+            return error_response(media_type, media_type.request_error_status(), &error);
+        }
+    };
+    if is_mutation(&request) {
+        let mut response = error_response(
+            media_type,
+            StatusCode::METHOD_NOT_ALLOWED,
+            &async_graphql::Error::new("Mutations should be sent with POST"),
+        );
+        response
+            .headers_mut()
+            .insert(header::ALLOW, HeaderValue::from_static("POST"));
+
+        return response;
+    }
+
+    execute(&schema, &authenticator, &headers, media_type, request).await
+}
+
+/// Serves queries and mutations sent with POST, as JSON, or as multipart requests uploading a file
+/// bounded by `multipart_options`
 pub(crate) async fn graphql_handler(
     Extension(schema): Extension<ServiceSchema>,
-    req: GraphQLRequest,
-) -> GraphQLResponse {
+    Extension(authenticator): Extension<Authenticator>,
+    Extension(persisted_queries): Extension<PersistedQueries>,
+    Extension(multipart_options): Extension<MultipartOptions>,
+    headers: HeaderMap,
+    request: axum::extract::Request,
+) -> Response {
+    let Some(media_type) = ResponseMediaType::negotiate(&headers) else {
+        return not_acceptable();
+    };
+    let request = match receive_request(request, multipart_options).await {
+        Ok(value) => value,
+        Err(error) => return GraphQLRejection(error).into_response(),
+    };
+
+    match persisted_queries.resolve(request) {
+        Ok(request) => execute(&schema, &authenticator, &headers, media_type, request).await,
+        Err(error) => error_response(media_type, media_type.request_error_status(), &error),
+    }
+}
+
+/// Read a GraphQL request from the body of `request`.  Multipart requests are bounded by
+/// `multipart_options` as they are read, so oversized uploads are rejected before they are written
+/// to disk in full.
+async fn receive_request(
+    request: axum::extract::Request,
+    multipart_options: MultipartOptions,
+) -> Result<async_graphql::Request, ParseRequestError> {
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string);
+    let body = request
+        .into_body()
+        .into_data_stream()
+        .map_err(std::io::Error::other)
+        .into_async_read();
+
+    receive_body(content_type, body, multipart_options).await
+}
+
+/// Identify the viewer from request `headers`, then execute `request` against `schema`, responding
+/// with `media_type`
+async fn execute(
+    schema: &ServiceSchema,
+    authenticator: &Authenticator,
+    headers: &HeaderMap,
+    media_type: ResponseMediaType,
+    request: async_graphql::Request,
+) -> Response {
+    let viewer = match authenticator.viewer(headers) {
+        Ok(value) => value,
+        Err(error) => {
+            let mut response = error_response(media_type, StatusCode::UNAUTHORIZED, &error);
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static(r#"Bearer error="invalid_token""#),
+            );
+
+            return response;
+        }
+    };
+
+    let authenticated = viewer != Viewer::Anonymous;
+
     let span = span!(Level::INFO, "graphql_execution");
+    // continue the caller's trace, when the request carries a `traceparent` header.  Setting the
+    // parent only fails when OpenTelemetry is not enabled, and there is no trace to continue.
+    let remote_context = remote_context(headers);
+    let _ = span.set_parent(remote_context.clone());
 
     tracing::info!("Processing GraphQL request");
 
-    let response = async move { schema.execute(req.into_inner()).await }
+    // attach the caller's context while executing, so resolvers can read its baggage from
+    // `opentelemetry::Context::current()`, with or without OpenTelemetry enabled
+    let response = async move { schema.execute(request.data(viewer)).await }
         .instrument(span.clone())
+        .with_context(remote_context.clone())
         .await;
 
     tracing::info!("Processing GraphQL request finished");
 
+    let cache_control = if response.is_ok() {
+        cache_control_value(response.cache_control, authenticated)
+    } else {
+        None
+    };
+    let status = if is_request_error(&response) {
+        media_type.request_error_status()
+    } else {
+        StatusCode::OK
+    };
+    // without OpenTelemetry, spans have no trace id, so report the caller's, if there is one
+    let trace_id = match span.context().span().span_context().trace_id() {
+        TraceId::INVALID => remote_context.span().span_context().trace_id(),
+        value => value,
+    };
+    let response = GraphQLResponse::from(response.extension(
+        "traceId",
+        async_graphql::Value::String(format!("{trace_id}")),
+    ));
+    let mut response = with_media_type((status, response).into_response(), media_type);
+
+    // replace any header set by `GraphQLResponse`, which omits the `public` scope.  The
+    // representation depends on `Accept`, and the content on `Authorization`, so shared caches
+    // must key on both.
+    let headers = response.headers_mut();
+    headers.remove(header::CACHE_CONTROL);
+    if let Some(value) = cache_control.and_then(|value| HeaderValue::from_str(&value).ok()) {
+        headers.insert(header::CACHE_CONTROL, value);
+        headers.insert(
+            header::VARY,
+            HeaderValue::from_static("Accept, Authorization"),
+        );
+    }
+
     response
-        .extension(
-            "traceId",
-            async_graphql::Value::String(format!(
-                "{}",
-                span.context().span().span_context().trace_id()
-            )),
-        )
-        .into()
+}
+
+/// `Cache-Control` header value for a successful response with the `cache_control` policy
+/// computed from field and type hints.  Responses to authenticated viewers may include content only
+/// they can see, so are always `private`.  Returns `None` when no field set a `max_age`.
+fn cache_control_value(cache_control: CacheControl, authenticated: bool) -> Option<String> {
+    let scope = if cache_control.public && !authenticated {
+        "public"
+    } else {
+        "private"
+    };
+
+    match cache_control.max_age {
+        -1 => Some(format!("no-cache, {scope}")),
+        max_age if max_age > 0 => Some(format!("{scope}, max-age={max_age}")),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `response` failed before execution started, for example because the
+/// document did not parse or validate.  Such responses have no `data`, and none of their errors
+/// relate to a field path.
+fn is_request_error(response: &async_graphql::Response) -> bool {
+    response.data == async_graphql::Value::Null
+        && !response.errors.is_empty()
+        && response.errors.iter().all(|error| error.path.is_empty())
+}
+
+/// Returns `true` if the operation `request` selects is a mutation.  Requests which do not parse
+/// are left for the schema to report on.
+fn is_mutation(request: &async_graphql::Request) -> bool {
+    let Ok(document) = parse_query(&request.query) else {
+        return false;
+    };
+
+    match (&document.operations, request.operation_name.as_deref()) {
+        (DocumentOperations::Single(operation), _) => operation.node.ty == OperationType::Mutation,
+        (DocumentOperations::Multiple(operations), Some(name)) => operations
+            .get(name)
+            .is_some_and(|operation| operation.node.ty == OperationType::Mutation),
+        (DocumentOperations::Multiple(_), None) => false,
+    }
+}
+
+/// Replace the `Content-Type` set by `GraphQLResponse` with the negotiated `media_type`
+fn with_media_type(mut response: Response, media_type: ResponseMediaType) -> Response {
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(media_type.content_type()),
+    );
+
+    response
+}
+
+/// GraphQL response with status `status`, holding only `error`, which relates to the request as a
+/// whole, so has no query location
+fn error_response<E: ErrorExtensions>(
+    media_type: ResponseMediaType,
+    status: StatusCode,
+    error: &E,
+) -> Response {
+    let response = async_graphql::Response::from_errors(vec![request_server_error(error)]);
+
+    with_media_type(
+        (status, GraphQLResponse::from(response)).into_response(),
+        media_type,
+    )
+}
+
+/// Server error for `error`, which relates to the request as a whole, so has no query location
+fn request_server_error<E: ErrorExtensions>(error: &E) -> ServerError {
+    let error = error.extend();
+    let mut server_error = ServerError::new(error.message, None);
+    server_error.extensions = error.extensions;
+
+    server_error
+}
+
+/// `400 Bad Request` GraphQL response for a malformed request
+fn bad_request(media_type: ResponseMediaType, message: &str) -> Response {
+    error_response(
+        media_type,
+        StatusCode::BAD_REQUEST,
+        &async_graphql::Error::new(message).extend_with(|_, extensions| {
+            extensions.set("code", "BAD_REQUEST");
+        }),
+    )
+}
+
+/// `406 Not Acceptable` response for a client which accepts no GraphQL response media type
+fn not_acceptable() -> Response {
+    (
+        StatusCode::NOT_ACCEPTABLE,
+        "Accept should include `application/graphql-response+json` or `application/json`",
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::CacheControl;
+
+    use super::cache_control_value;
+
+    #[test]
+    fn cache_control_value_applies_scope_and_max_age() {
+        // arrange
+        let public = CacheControl {
+            public: true,
+            max_age: 60,
+        };
+        let private = CacheControl {
+            public: false,
+            max_age: 60,
+        };
+
+        // act
+        let anonymous_public = cache_control_value(public, false);
+        let authenticated_public = cache_control_value(public, true);
+        let anonymous_private = cache_control_value(private, false);
+
+        // assert
+        assert_eq!(anonymous_public.as_deref(), Some("public, max-age=60"));
+        assert_eq!(authenticated_public.as_deref(), Some("private, max-age=60"));
+        assert_eq!(anonymous_private.as_deref(), Some("private, max-age=60"));
+    }
+
+    #[test]
+    fn cache_control_value_handles_no_cache_and_unset_max_age() {
+        // arrange
+        let no_cache = CacheControl {
+            public: true,
+            max_age: -1,
+        };
+
+        // act
+        let no_cache_outcome = cache_control_value(no_cache, false);
+        let unset_outcome = cache_control_value(CacheControl::default(), false);
+
+        // assert
+        assert_eq!(no_cache_outcome.as_deref(), Some("no-cache, public"));
+        assert_eq!(unset_outcome, None);
+    }
 }
@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    num::NonZeroUsize,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use anyhow::Context as _;
+use async_graphql::ErrorExtensions;
+use lru::LruCache;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Default number of queries remembered by [`PersistedQueries::automatic`]
+pub const DEFAULT_PERSISTED_QUERY_CAPACITY: usize = 1_024;
+
+/// Contents of the `persistedQuery` request extension, sent by Apollo clients
+#[derive(Deserialize)]
+struct PersistedQueryExtension {
+    version: i32,
+
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+/// Reasons a persisted query request cannot be served, reported to GraphQL clients with an error
+/// extension `code`.  Apollo clients watch for the `PersistedQueryNotFound` message, and retry
+/// with the full query.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PersistedQueryError {
+    /// Request only sent a hash, and no query is stored for it
+    NotFound,
+
+    /// Allowlist mode is enabled, and the query is not in the allowlist
+    NotAllowed,
+
+    /// Request sent a full query, but its hash does not match the one sent alongside it
+    HashMismatch,
+
+    /// The `persistedQuery` extension is malformed, or not version 1
+    Invalid,
+}
+
+impl PersistedQueryError {
+    /// Error extension code reported to GraphQL clients
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "PERSISTED_QUERY_NOT_FOUND",
+            Self::NotAllowed => "PERSISTED_QUERY_NOT_ALLOWED",
+            Self::HashMismatch | Self::Invalid => "BAD_USER_INPUT",
+        }
+    }
+}
+
+impl fmt::Display for PersistedQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("PersistedQueryNotFound"),
+            Self::NotAllowed => f.write_str("PersistedQueryNotAllowed"),
+            Self::HashMismatch => f.write_str("provided sha does not match query"),
+            Self::Invalid => f.write_str(
+                "`persistedQuery` extension should be version 1 and include a `sha256Hash`",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PersistedQueryError {}
+
+impl ErrorExtensions for PersistedQueryError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, extensions| {
+            extensions.set("code", self.code());
+        })
+    }
+}
+
+/// Query text keyed by its hex-encoded SHA-256 hash
+#[derive(Clone)]
+enum Store {
+    /// Queries registered by clients as they are sent, evicting the least recently used once full
+    Automatic(Arc<Mutex<LruCache<String, String>>>),
+
+    /// Fixed set of queries loaded at startup.  No other queries may run.
+    Allowlist(Arc<HashMap<String, String>>),
+}
+
+/// Serves the Apollo automatic persisted query (APQ) protocol, where clients send the SHA-256
+/// hash of a query in the `persistedQuery` request extension, rather than the full query text.
+#[derive(Clone)]
+pub struct PersistedQueries {
+    store: Store,
+}
+
+impl Default for PersistedQueries {
+    fn default() -> Self {
+        Self::automatic(DEFAULT_PERSISTED_QUERY_CAPACITY)
+    }
+}
+
+impl PersistedQueries {
+    /// Remember up to `capacity` queries registered by clients
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    #[must_use]
+    pub fn automatic(capacity: usize) -> Self {
+        let capacity =
+            NonZeroUsize::new(capacity).expect("persisted query capacity should be non-zero");
+
+        Self {
+            store: Store::Automatic(Arc::new(Mutex::new(LruCache::new(capacity)))),
+        }
+    }
+
+    /// Only allow the queries in the JSON file at `path`, which holds an object mapping
+    /// hex-encoded SHA-256 hashes to query text
+    ///
+    /// # Errors
+    ///
+    /// Errors if the file cannot be read or parsed, or if a hash does not match its query.
+    pub fn allowlist_from_file<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("read persisted query allowlist `{}`", path.display()))?;
+        let queries: HashMap<String, String> = serde_json::from_slice(&contents)
+            .with_context(|| format!("parse persisted query allowlist `{}`", path.display()))?;
+
+        if let Some(hash) = queries
+            .iter()
+            .find_map(|(hash, query)| (*hash != sha256_hash(query)).then_some(hash))
+        {
+            anyhow::bail!("persisted query allowlist hash `{hash}` does not match its query");
+        }
+
+        Ok(Self {
+            store: Store::Allowlist(Arc::new(queries)),
+        })
+    }
+
+    /// Configure from the environment, using the allowlist file at `PERSISTED_QUERIES_ALLOWLIST`
+    /// if set, otherwise an automatic store holding up to `PERSISTED_QUERIES_CAPACITY` queries.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the allowlist file cannot be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `PERSISTED_QUERIES_CAPACITY` exists, but is not a positive integer.
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        if let Ok(path) = std::env::var("PERSISTED_QUERIES_ALLOWLIST") {
+            return Self::allowlist_from_file(path);
+        }
+        let capacity = std::env::var("PERSISTED_QUERIES_CAPACITY").map_or(
+            DEFAULT_PERSISTED_QUERY_CAPACITY,
+            |value| {
+                value.parse().expect(
+                    "`PERSISTED_QUERIES_CAPACITY` env variable should be a positive integer",
+                )
+            },
+        );
+
+        Ok(Self::automatic(capacity))
+    }
+
+    /// Returns `true` if the request carries a `persistedQuery` extension
+    #[must_use]
+    pub fn is_persisted_query(request: &async_graphql::Request) -> bool {
+        request.extensions.contains_key("persistedQuery")
+    }
+
+    /// Fill in the query text of a request which only sent a hash, and register the query of a
+    /// request which sent both.  In allowlist mode, rejects any query not in the allowlist.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the hash is unknown, does not match the query, or the query is not allowed.
+    pub fn resolve(
+        &self,
+        mut request: async_graphql::Request,
+    ) -> Result<async_graphql::Request, PersistedQueryError> {
+        let Some(value) = request.extensions.remove("persistedQuery") else {
+            return match &self.store {
+                Store::Automatic(_) => Ok(request),
+                Store::Allowlist(queries) if queries.contains_key(&sha256_hash(&request.query)) => {
+                    Ok(request)
+                }
+                Store::Allowlist(_) => Err(PersistedQueryError::NotAllowed),
+            };
+        };
+        let extension: PersistedQueryExtension =
+            async_graphql::from_value(value).map_err(|_| PersistedQueryError::Invalid)?;
+        if extension.version != 1 {
+            return Err(PersistedQueryError::Invalid);
+        }
+
+        if request.query.is_empty() {
+            request.query = self
+                .get(&extension.sha256_hash)
+                .ok_or(PersistedQueryError::NotFound)?;
+
+            return Ok(request);
+        }
+
+        if sha256_hash(&request.query) != extension.sha256_hash {
+            return Err(PersistedQueryError::HashMismatch);
+        }
+        match &self.store {
+            Store::Automatic(cache) => {
+                cache
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .put(extension.sha256_hash, request.query.clone());
+            }
+            Store::Allowlist(queries) if queries.contains_key(&extension.sha256_hash) => {}
+            Store::Allowlist(_) => return Err(PersistedQueryError::NotAllowed),
+        }
+
+        Ok(request)
+    }
+
+    fn get(&self, hash: &str) -> Option<String> {
+        match &self.store {
+            Store::Automatic(cache) => cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(hash)
+                .cloned(),
+            Store::Allowlist(queries) => queries.get(hash).cloned(),
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 hash of `query`
+fn sha256_hash(query: &str) -> String {
+    format!("{:x}", Sha256::digest(query.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{Request, Value, value};
+
+    use super::{PersistedQueries, PersistedQueryError, sha256_hash};
+
+    const QUERY: &str = "query HelloQuery { hello }";
+
+    fn hash_only_request(hash: &str) -> Request {
+        let mut request = Request::new("");
+        request.extensions.insert(
+            String::from("persistedQuery"),
+            value!({ "version": 1, "sha256Hash": hash }),
+        );
+
+        request
+    }
+
+    #[test]
+    fn resolve_registers_query_then_serves_it_by_hash() {
+        // arrange
+        let persisted_queries = PersistedQueries::automatic(2);
+        let hash = sha256_hash(QUERY);
+        let mut registration = Request::new(QUERY);
+        registration.extensions.insert(
+            String::from("persistedQuery"),
+            value!({ "version": 1, "sha256Hash": hash.clone() }),
+        );
+
+        // act
+        let before_registration = persisted_queries.resolve(hash_only_request(&hash));
+        persisted_queries.resolve(registration).unwrap();
+        let after_registration = persisted_queries.resolve(hash_only_request(&hash)).unwrap();
+
+        // assert
+        assert_eq!(
+            before_registration.err(),
+            Some(PersistedQueryError::NotFound)
+        );
+        assert_eq!(after_registration.query, QUERY);
+        assert!(!after_registration.extensions.contains_key("persistedQuery"));
+    }
+
+    #[test]
+    fn resolve_evicts_least_recently_used_query_when_full() {
+        // arrange
+        let persisted_queries = PersistedQueries::automatic(1);
+        for query in [QUERY, "query { __typename }"] {
+            let mut request = Request::new(query);
+            request.extensions.insert(
+                String::from("persistedQuery"),
+                value!({ "version": 1, "sha256Hash": sha256_hash(query) }),
+            );
+            persisted_queries.resolve(request).unwrap();
+        }
+
+        // act
+        let outcome = persisted_queries.resolve(hash_only_request(&sha256_hash(QUERY)));
+
+        // assert
+        assert_eq!(outcome.err(), Some(PersistedQueryError::NotFound));
+    }
+
+    #[test]
+    fn resolve_rejects_mismatched_hash_and_unsupported_version() {
+        // arrange
+        let persisted_queries = PersistedQueries::default();
+        let mut mismatched = Request::new(QUERY);
+        mismatched.extensions.insert(
+            String::from("persistedQuery"),
+            value!({ "version": 1, "sha256Hash": "0000" }),
+        );
+        let mut unsupported = hash_only_request(&sha256_hash(QUERY));
+        unsupported.extensions.insert(
+            String::from("persistedQuery"),
+            value!({ "version": 2, "sha256Hash": sha256_hash(QUERY) }),
+        );
+        let mut malformed = Request::new(QUERY);
+        malformed
+            .extensions
+            .insert(String::from("persistedQuery"), Value::Null);
+
+        // act
+        let mismatched_outcome = persisted_queries.resolve(mismatched);
+        let unsupported_outcome = persisted_queries.resolve(unsupported);
+        let malformed_outcome = persisted_queries.resolve(malformed);
+
+        // assert
+        assert_eq!(
+            mismatched_outcome.err(),
+            Some(PersistedQueryError::HashMismatch)
+        );
+        assert_eq!(
+            unsupported_outcome.err(),
+            Some(PersistedQueryError::Invalid)
+        );
+        assert_eq!(malformed_outcome.err(), Some(PersistedQueryError::Invalid));
+    }
+}
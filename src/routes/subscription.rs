@@ -0,0 +1,97 @@
+use std::{pin::Pin, sync::Arc};
+
+use async_graphql::{Data, ErrorExtensions, Executor, Request, http::ALL_WEBSOCKET_PROTOCOLS};
+use async_graphql_axum::{GraphQLProtocol, GraphQLWebSocket};
+use axum::{
+    extract::{Extension, WebSocketUpgrade},
+    response::Response,
+};
+use tokio_stream::{Stream, once};
+
+use super::{persisted_queries::PersistedQueries, request_server_error};
+use crate::{
+    auth::{AuthError, Authenticator, Viewer},
+    model::ServiceSchema,
+};
+
+/// Executes operations sent over a WebSocket against `schema`, after resolving persisted queries
+/// the same way the HTTP handlers do, so allowlist mode also applies to subscriptions
+#[derive(Clone)]
+struct PersistedQueryExecutor {
+    schema: ServiceSchema,
+    persisted_queries: PersistedQueries,
+}
+
+impl Executor for PersistedQueryExecutor {
+    async fn execute(&self, request: Request) -> async_graphql::Response {
+        match self.persisted_queries.resolve(request) {
+            Ok(request) => self.schema.execute(request).await,
+            Err(error) => async_graphql::Response::from_errors(vec![request_server_error(&error)]),
+        }
+    }
+
+    fn execute_stream(
+        &self,
+        request: Request,
+        session_data: Option<Arc<Data>>,
+    ) -> Pin<Box<dyn Stream<Item = async_graphql::Response> + Send + 'static>> {
+        match self.persisted_queries.resolve(request) {
+            Ok(request) => Executor::execute_stream(&self.schema, request, session_data),
+            Err(error) => Box::pin(once(async_graphql::Response::from_errors(vec![
+                request_server_error(&error),
+            ]))),
+        }
+    }
+}
+
+/// Serves subscriptions over WebSocket.  Browsers cannot set headers on WebSocket requests, so
+/// the viewer is identified from an `Authorization` entry in the `connection_init` payload
+/// instead, and connections presenting an invalid bearer token are closed.  Each operation is
+/// checked against `persisted_queries`, as for HTTP requests.
+pub(crate) async fn graphql_ws_handler(
+    Extension(schema): Extension<ServiceSchema>,
+    Extension(authenticator): Extension<Authenticator>,
+    Extension(persisted_queries): Extension<PersistedQueries>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let executor = PersistedQueryExecutor {
+        schema,
+        persisted_queries,
+    };
+
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| {
+            GraphQLWebSocket::new(stream, executor, protocol)
+                .on_connection_init(async move |payload| {
+                    let viewer = connection_viewer(&authenticator, &payload)
+                        .map_err(|error| error.extend())?;
+                    let mut data = Data::default();
+                    data.insert(viewer);
+
+                    Ok(data)
+                })
+                .serve()
+        })
+}
+
+/// Identify the viewer from the `Authorization` entry, matched case-insensitively, of a
+/// `connection_init` `payload`.  Connections without the entry are anonymous.
+fn connection_viewer(
+    authenticator: &Authenticator,
+    payload: &serde_json::Value,
+) -> Result<Viewer, AuthError> {
+    let authorization = payload.as_object().and_then(|entries| {
+        entries
+            .iter()
+            .find_map(|(key, value)| key.eq_ignore_ascii_case("authorization").then_some(value))
+    });
+
+    match authorization {
+        None => Ok(Viewer::Anonymous),
+        Some(value) => {
+            authenticator.authorization_viewer(value.as_str().ok_or(AuthError::InvalidToken)?)
+        }
+    }
+}
@@ -1,14 +1,73 @@
 use axum::{Router, serve::Serve};
 use sqlx::SqlitePool;
-use tokio::{net::TcpListener, signal};
+use tokio::{net::TcpListener, signal, sync::watch};
 
 use crate::{
+    auth::Authenticator,
     database::run_migrations,
-    model::get_schema,
-    observability::{OpenTelemetryProviders, shutdown_opentelemetry_providers},
-    router::init_router,
+    model::{
+        cover_image::CoverImageStorage, events::PostEventBroker, get_schema, limits::QueryLimits,
+    },
+    observability::{
+        OpenTelemetryProviders, metrics::MetricsEndpoint, shutdown_opentelemetry_providers,
+    },
+    router::{init_router, metrics_router},
+    routes::persisted_queries::PersistedQueries,
+    workers::{
+        scheduled_publish::{ScheduledPublishConfig, run_scheduled_publish},
+        trash_purge::{TrashPurgeConfig, run_trash_purge},
+    },
 };
 
+/// Runtime configuration for the app
+#[derive(Clone, Default)]
+pub struct ApplicationConfig {
+    /// Validates bearer tokens presented by callers
+    pub authenticator: Authenticator,
+
+    /// Bounds on the depth, complexity and aliases of GraphQL operations
+    pub query_limits: QueryLimits,
+
+    /// Store of automatic persisted queries, or allowlist of the only queries which may run
+    pub persisted_queries: PersistedQueries,
+
+    /// Directory and size limit for uploaded cover images
+    pub cover_images: CoverImageStorage,
+
+    /// Retention period and schedule for purging deleted drafts from the trash
+    pub trash_purge: TrashPurgeConfig,
+
+    /// How often to check for scheduled drafts which are due to be published
+    pub scheduled_publish: ScheduledPublishConfig,
+
+    /// Where the Prometheus `/metrics` scrape endpoint is served, if at all
+    pub metrics_endpoint: MetricsEndpoint,
+}
+
+impl ApplicationConfig {
+    /// Read configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the configured JWKS file or persisted query allowlist cannot be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a query limit, upload size, trash purge or scheduled publish environment variable
+    /// is not an integer, or if a Prometheus environment variable is not valid.
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            authenticator: Authenticator::from_env()?,
+            query_limits: QueryLimits::from_env(),
+            persisted_queries: PersistedQueries::from_env()?,
+            cover_images: CoverImageStorage::from_env(),
+            trash_purge: TrashPurgeConfig::from_env(),
+            scheduled_publish: ScheduledPublishConfig::from_env(),
+            metrics_endpoint: MetricsEndpoint::from_env(),
+        })
+    }
+}
+
 pub struct ApplicationRouter {
     pub router: Router,
 }
@@ -18,9 +77,12 @@ impl ApplicationRouter {
     ///
     /// # Errors
     /// Returns an error if the database is not reachable
-    pub async fn build(database_url: &str) -> Result<Self, std::io::Error> {
+    pub async fn build(
+        database_url: &str,
+        config: ApplicationConfig,
+    ) -> Result<Self, std::io::Error> {
         Ok(Self {
-            router: router(database_url).await,
+            router: router(database_url, config).await,
         })
     }
 }
@@ -67,6 +129,14 @@ pub async fn shutdown_signal(open_telemetry_providers: Option<OpenTelemetryProvi
 pub struct Application {
     pub server: Serve<TcpListener, Router, Router>,
     pub port: u16,
+
+    /// Port of the admin listener serving the Prometheus scrape endpoint, if it has one
+    pub metrics_port: Option<u16>,
+    metrics_server: Option<Serve<TcpListener, Router, Router>>,
+    db_pool: SqlitePool,
+    events: PostEventBroker,
+    trash_purge: TrashPurgeConfig,
+    scheduled_publish: ScheduledPublishConfig,
 }
 
 impl Application {
@@ -79,11 +149,18 @@ impl Application {
     /// Errors if the listen port or address is invalid.
     pub async fn build(
         database_url: &str,
+        config: ApplicationConfig,
         (listener_ip, listener_port): (&str, u16),
     ) -> Result<Self, std::io::Error> {
-        let ApplicationRouter { router } = ApplicationRouter::build(database_url)
-            .await
-            .expect("database should be reachable");
+        let db_pool = connect(database_url).await;
+        let events = PostEventBroker::default();
+        let ApplicationConfig {
+            trash_purge,
+            scheduled_publish,
+            metrics_endpoint,
+            ..
+        } = config;
+        let router = app_router(db_pool.clone(), events.clone(), config);
 
         let listener = TcpListener::bind(format!("{listener_ip}:{listener_port}"))
             .await
@@ -93,13 +170,37 @@ impl Application {
         let port = listener.local_addr().unwrap().port();
         tracing::info!("App service listening on {}", listener.local_addr()?);
 
+        let (metrics_server, metrics_port) =
+            if let MetricsEndpoint::AdminListener(address) = metrics_endpoint {
+                let listener = TcpListener::bind(address)
+                    .await
+                    .unwrap_or_else(|_| panic!("`{address}` should not already be in use"));
+                let metrics_port = listener.local_addr()?.port();
+                tracing::info!("Metrics service listening on {}", listener.local_addr()?);
+
+                (
+                    Some(axum::serve(listener, metrics_router())),
+                    Some(metrics_port),
+                )
+            } else {
+                (None, None)
+            };
+
         Ok(Self {
             server: axum::serve(listener, router),
             port,
+            metrics_port,
+            metrics_server,
+            db_pool,
+            events,
+            trash_purge,
+            scheduled_publish,
         })
     }
 
-    /// Run the app.  Can be used in tests and when running the app in production.
+    /// Run the app, along with background tasks purging deleted drafts from the trash and
+    /// publishing scheduled drafts, which are stopped once the server shuts down.  Can be used in
+    /// tests and when running the app in production.
     ///
     /// # Errors
     ///
@@ -109,11 +210,56 @@ impl Application {
         self,
         opentelemetry_providers: Option<OpenTelemetryProviders>,
     ) -> Result<(), std::io::Error> {
-        self.server
+        let Self {
+            server,
+            metrics_server,
+            db_pool,
+            events,
+            trash_purge,
+            scheduled_publish,
+            ..
+        } = self;
+        let (stop_background, background_stopped) = watch::channel(false);
+        let metrics_server = metrics_server.map(|value| {
+            let mut stopped = background_stopped.clone();
+            tokio::spawn(
+                value
+                    .with_graceful_shutdown(async move {
+                        let _ = stopped.changed().await;
+                    })
+                    .into_future(),
+            )
+        });
+        let workers = [
+            tokio::spawn(run_trash_purge(
+                db_pool.clone(),
+                trash_purge,
+                background_stopped.clone(),
+            )),
+            tokio::spawn(run_scheduled_publish(
+                db_pool,
+                events,
+                scheduled_publish,
+                background_stopped,
+            )),
+        ];
+
+        let result = server
             .with_graceful_shutdown(shutdown_signal(opentelemetry_providers.clone()))
-            .await?;
+            .await;
 
-        Ok(())
+        // let workers finish any run already under way, so no transaction is cut short
+        stop_background.send_replace(true);
+        for worker in workers {
+            if let Err(error) = worker.await {
+                tracing::error!("Background worker failed: {error}");
+            }
+        }
+        if let Some(value) = metrics_server {
+            value.await??;
+        }
+
+        result
     }
 }
 
@@ -123,7 +269,14 @@ impl Application {
 /// Panics when not able to reach the database.
 ///
 /// Panics if .
-pub async fn router(database_url: &str) -> Router {
+pub async fn router(database_url: &str, config: ApplicationConfig) -> Router {
+    let db_pool = connect(database_url).await;
+
+    app_router(db_pool, PostEventBroker::default(), config)
+}
+
+/// Connect to the database at `database_url`, running any pending migrations
+async fn connect(database_url: &str) -> SqlitePool {
     tracing::info!("App service starting");
 
     let db_pool = SqlitePool::connect(database_url)
@@ -131,7 +284,28 @@ pub async fn router(database_url: &str) -> Router {
         .expect("SQLite database should be reachable");
     run_migrations(&db_pool).await;
 
-    let schema = get_schema(db_pool);
+    db_pool
+}
+
+/// Create the main app axum router, serving data from `db_pool`, with subscriptions streaming
+/// events sent through `events`
+fn app_router(db_pool: SqlitePool, events: PostEventBroker, config: ApplicationConfig) -> Router {
+    let ApplicationConfig {
+        authenticator,
+        query_limits,
+        persisted_queries,
+        cover_images,
+        trash_purge: _,
+        scheduled_publish: _,
+        metrics_endpoint,
+    } = config;
+    let schema = get_schema(db_pool, query_limits, cover_images.clone(), events);
 
-    init_router(schema)
+    init_router(
+        schema,
+        authenticator,
+        persisted_queries,
+        &cover_images,
+        metrics_endpoint,
+    )
 }
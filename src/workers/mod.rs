@@ -0,0 +1,2 @@
+pub mod scheduled_publish;
+pub mod trash_purge;
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+
+use crate::model::{
+    events::{PostEvent, PostEventBroker},
+    schedule::publish_due_posts_mutation,
+};
+
+/// Default time between checks for drafts due to be published
+pub const DEFAULT_SCHEDULED_PUBLISH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often to check for scheduled drafts which are due to be published
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledPublishConfig {
+    /// Time between checks, which bounds how late a draft may be published
+    pub interval: Duration,
+}
+
+impl Default for ScheduledPublishConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_SCHEDULED_PUBLISH_INTERVAL,
+        }
+    }
+}
+
+impl ScheduledPublishConfig {
+    /// Read the time between checks, in seconds, from `SCHEDULED_PUBLISH_INTERVAL_SECONDS`,
+    /// falling back to the default if it is not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `SCHEDULED_PUBLISH_INTERVAL_SECONDS` exists, but is not a positive integer.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            interval: std::env::var("SCHEDULED_PUBLISH_INTERVAL_SECONDS").map_or(
+                default.interval,
+                |value| {
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|seconds| *seconds > 0)
+                        .map(Duration::from_secs)
+                        .expect(
+                            "`SCHEDULED_PUBLISH_INTERVAL_SECONDS` env variable should be a \
+                             positive integer",
+                        )
+                },
+            ),
+        }
+    }
+}
+
+/// Publish scheduled drafts once they are due, checking once on start, to catch up on any missed
+/// while the app was stopped, then every `interval`.  Published posts are sent to subscribers
+/// through `events`.  Runs until `shutdown` changes, finishing any check already under way.
+pub async fn run_scheduled_publish(
+    db_pool: SqlitePool,
+    events: PostEventBroker,
+    config: ScheduledPublishConfig,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => publish_due_posts(&db_pool, &events).await,
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Publish drafts which are due, logging, rather than returning, any error, so a failed check is
+/// retried on the next tick.  Each published post gets its own span, recording when it was
+/// scheduled for.
+#[tracing::instrument(name = "Scheduled publish", skip(db_pool, events))]
+async fn publish_due_posts(db_pool: &SqlitePool, events: &PostEventBroker) {
+    match publish_due_posts_mutation(db_pool, Utc::now()).await {
+        Ok(posts) => {
+            for post in posts {
+                // publishing copies the schedule to `published_at`, before clearing it
+                let scheduled_at = post.published_at.map(|at| at.to_rfc3339());
+                let _span =
+                    tracing::info_span!("Publish scheduled post", post.id, scheduled_at).entered();
+                tracing::info!("Published scheduled post");
+                events.publish(PostEvent::Published(post));
+            }
+        }
+        Err(error) => tracing::error!("Failed to publish scheduled posts: {error:?}"),
+    }
+}
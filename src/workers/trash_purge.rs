@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+
+use crate::model::post::purge_deleted_drafts_mutation;
+
+/// Default time deleted drafts are kept in the trash before they are purged
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// Default time between purges
+pub const DEFAULT_TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long deleted drafts are kept in the trash, and how often the trash is purged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrashPurgeConfig {
+    /// Time a deleted draft is kept before it is purged
+    pub retention: TimeDelta,
+
+    /// Time between purges
+    pub interval: Duration,
+}
+
+impl Default for TrashPurgeConfig {
+    fn default() -> Self {
+        Self {
+            retention: TimeDelta::days(DEFAULT_TRASH_RETENTION_DAYS.into()),
+            interval: DEFAULT_TRASH_PURGE_INTERVAL,
+        }
+    }
+}
+
+impl TrashPurgeConfig {
+    /// Read the retention period, in days, from `TRASH_RETENTION_DAYS` and the time between
+    /// purges, in seconds, from `TRASH_PURGE_INTERVAL_SECONDS`, falling back to defaults for any
+    /// which are not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `TRASH_RETENTION_DAYS` exists, but is not a non-negative integer, or if
+    /// `TRASH_PURGE_INTERVAL_SECONDS` exists, but is not a positive integer.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            retention: std::env::var("TRASH_RETENTION_DAYS").map_or(default.retention, |value| {
+                let days: u32 = value
+                    .parse()
+                    .expect("`TRASH_RETENTION_DAYS` env variable should be an integer");
+                TimeDelta::days(days.into())
+            }),
+            interval: std::env::var("TRASH_PURGE_INTERVAL_SECONDS").map_or(
+                default.interval,
+                |value| {
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|seconds| *seconds > 0)
+                        .map(Duration::from_secs)
+                        .expect(
+                            "`TRASH_PURGE_INTERVAL_SECONDS` env variable should be a positive \
+                             integer",
+                        )
+                },
+            ),
+        }
+    }
+}
+
+/// Purge drafts which have been in the trash for longer than the configured retention period,
+/// once on start, then every `interval`.  Runs until `shutdown` changes, finishing any purge
+/// already under way.
+pub async fn run_trash_purge(
+    db_pool: SqlitePool,
+    config: TrashPurgeConfig,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => purge_trash(&db_pool, config.retention).await,
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Purge drafts deleted more than `retention` ago, logging, rather than returning, any error, so
+/// a failed purge is retried on the next tick
+#[tracing::instrument(name = "Trash purge", skip(db_pool))]
+async fn purge_trash(db_pool: &SqlitePool, retention: TimeDelta) {
+    let Some(deleted_before) = Utc::now().checked_sub_signed(retention) else {
+        return;
+    };
+
+    match purge_deleted_drafts_mutation(db_pool, deleted_before).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Purged {count} deleted drafts"),
+        Err(error) => tracing::error!("Failed to purge deleted drafts: {error:?}"),
+    }
+}
@@ -1,43 +1,58 @@
 use std::sync::LazyLock;
 
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde_json::json;
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
 
 use axum_graphql::{
+    auth::Authenticator,
     database::run_migrations,
     observability::{OpenTelemetryProviders, initialise_observability},
-    startup::{Application, ApplicationRouter},
+    startup::{Application, ApplicationConfig, ApplicationRouter},
 };
 
+/// HS256 secret trusted by test apps for signing bearer tokens
+const TEST_JWT_SECRET: &[u8] = b"test-jwt-secret";
+
 static TRACING: LazyLock<Option<OpenTelemetryProviders>> = LazyLock::new(initialise_observability);
 
 pub struct TestApp {
     pub port: u16,
+    pub metrics_port: Option<u16>,
 }
 
 impl TestApp {
     pub async fn spawn() -> Self {
+        Self::spawn_with_config(Self::config()).await
+    }
+
+    /// Spawn the app with `config`, rather than the default test configuration
+    pub async fn spawn_with_config(config: ApplicationConfig) -> Self {
         unsafe {
             std::env::set_var("OPENTELEMETRY_ENABLED", "true");
+            std::env::set_var("PROMETHEUS_ENABLED", "true");
         }
         let tracer_provider = LazyLock::force(&TRACING);
         let database_url = "sqlite://:memory:";
 
-        let app = Application::build(database_url, ("127.0.0.1", 0))
+        let app = Application::build(database_url, config, ("127.0.0.1", 0))
             .await
             .unwrap();
 
-        let Application { port, .. } = app;
+        let Application {
+            port, metrics_port, ..
+        } = app;
 
         #[expect(clippy::let_underscore_future)]
         let _ = tokio::spawn(app.run_until_stopped(tracer_provider.clone()));
 
-        Self { port }
+        Self { port, metrics_port }
     }
 
     pub async fn spawn_routers() -> ApplicationRouter {
         let database_url = "sqlite://:memory:";
 
-        ApplicationRouter::build(database_url)
+        ApplicationRouter::build(database_url, Self::config())
             .await
             .expect("database should be reachable")
     }
@@ -57,4 +72,35 @@ impl TestApp {
 
         db_pool
     }
+
+    /// App configuration, trusting tokens signed with the test secret
+    pub fn config() -> ApplicationConfig {
+        ApplicationConfig {
+            authenticator: Authenticator::from_secret(TEST_JWT_SECRET),
+            ..ApplicationConfig::default()
+        }
+    }
+
+    /// `Authorization` header value, with a bearer token for an admin and editor with subject
+    /// `admin`, which is not a user id, valid for an hour
+    pub fn authorization_header() -> String {
+        Self::authorization_header_for("admin", &["admin", "editor"])
+    }
+
+    /// `Authorization` header value, with a bearer token for `subject`, granting `roles`, valid
+    /// for an hour
+    pub fn authorization_header_for(subject: &str, roles: &[&str]) -> String {
+        let token = encode(
+            &Header::default(),
+            &json!({
+                "sub": subject,
+                "exp": chrono::Utc::now().timestamp() + 3_600,
+                "roles": roles
+            }),
+            &EncodingKey::from_secret(TEST_JWT_SECRET),
+        )
+        .unwrap();
+
+        format!("Bearer {token}")
+    }
 }
@@ -4,3 +4,4 @@ mod helpers;
 mod model;
 mod routes;
 mod startup;
+mod workers;
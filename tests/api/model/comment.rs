@@ -0,0 +1,158 @@
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    comment::{
+        AddCommentErrorResponse, AddCommentResponse, AddCommentSuccessResponse, Comment,
+        CommentFilter, CommentPage, CommentStatus, ModerateCommentResponse, add_comment_mutation,
+        comments_query, moderate_comment_mutation,
+    },
+    post::{Post, UserInputError, create_draft_mutation, publish_mutation},
+};
+
+/// Create and publish a post, returning its `id`
+async fn published_post(db_pool: &sqlx::SqlitePool) -> i64 {
+    let Post { id, .. } = create_draft_mutation(db_pool, "Published Post", "Post body.", None)
+        .await
+        .unwrap();
+    publish_mutation(db_pool, id).await.unwrap();
+
+    id
+}
+
+#[tokio::test]
+async fn add_comment_mutation_adds_pending_comment_to_published_post() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let post_id = published_post(&db_pool).await;
+
+    // act
+    let outcome = add_comment_mutation(&db_pool, post_id, "Ada", "Great post!")
+        .await
+        .unwrap();
+
+    // assert
+    let AddCommentResponse::AddCommentSuccessResponse(AddCommentSuccessResponse {
+        comment:
+            Comment {
+                post_id: comment_post_id,
+                author_name,
+                body,
+                status,
+                ..
+            },
+    }) = outcome
+    else {
+        panic!("expected success response");
+    };
+    assert_eq!(comment_post_id, post_id);
+    assert_eq!(author_name, "Ada");
+    assert_eq!(body, "Great post!");
+    assert_eq!(status, CommentStatus::Pending);
+}
+
+#[tokio::test]
+async fn add_comment_mutation_returns_user_error_for_draft_or_missing_post() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Draft Post", "Draft body.", None)
+        .await
+        .unwrap();
+
+    // act
+    let draft = add_comment_mutation(&db_pool, id, "Ada", "Great post!")
+        .await
+        .unwrap();
+    let missing = add_comment_mutation(&db_pool, 9_999, "Ada", "Great post!")
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(
+        draft,
+        AddCommentResponse::AddCommentErrorResponse(AddCommentErrorResponse {
+            error: UserInputError {
+                field: String::from("postId"),
+                message: format!("Post with id `{id}` is not published"),
+                received: id.to_string(),
+            }
+        })
+    );
+    assert_eq!(
+        missing,
+        AddCommentResponse::AddCommentErrorResponse(AddCommentErrorResponse {
+            error: UserInputError {
+                field: String::from("postId"),
+                message: String::from("Did not find published post with id `9999`"),
+                received: String::from("9999"),
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn comments_query_filters_by_status_and_pages_by_id() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let post_id = published_post(&db_pool).await;
+    let mut ids = Vec::new();
+    for body in ["First", "Second", "Third", "Fourth"] {
+        let AddCommentResponse::AddCommentSuccessResponse(AddCommentSuccessResponse { comment }) =
+            add_comment_mutation(&db_pool, post_id, "Ada", body)
+                .await
+                .unwrap()
+        else {
+            panic!("expected success response");
+        };
+        ids.push(comment.id);
+    }
+    for (id, status) in [
+        (ids[0], CommentStatus::Approved),
+        (ids[1], CommentStatus::Rejected),
+        (ids[2], CommentStatus::Approved),
+        (ids[3], CommentStatus::Approved),
+    ] {
+        moderate_comment_mutation(&db_pool, id, status)
+            .await
+            .unwrap();
+    }
+    let filter = CommentFilter {
+        post_id: Some(post_id),
+        status: Some(CommentStatus::Approved),
+    };
+
+    // act
+    let first_page = comments_query(&db_pool, filter, None, Some(2))
+        .await
+        .unwrap();
+    let second_page = comments_query(&db_pool, filter, Some(ids[2]), Some(2))
+        .await
+        .unwrap();
+
+    // assert
+    let bodies = |page: &CommentPage| {
+        page.comments
+            .iter()
+            .map(|comment| comment.body.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bodies(&first_page), vec!["First", "Third"]);
+    assert!(first_page.has_next_page);
+    assert_eq!(bodies(&second_page), vec!["Fourth"]);
+    assert!(!second_page.has_next_page);
+}
+
+#[tokio::test]
+async fn moderate_comment_mutation_returns_user_error_for_missing_comment() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+
+    // act
+    let outcome = moderate_comment_mutation(&db_pool, 9_999, CommentStatus::Approved)
+        .await
+        .unwrap();
+
+    // assert
+    let ModerateCommentResponse::ModerateCommentErrorResponse(outcome) = outcome else {
+        panic!("expected error response");
+    };
+    assert_eq!(outcome.error.message, "Did not find comment with id `9999`");
+}
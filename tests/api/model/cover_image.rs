@@ -0,0 +1,135 @@
+use assert_fs::prelude::*;
+use async_graphql::{UploadValue, dataloader::Loader};
+use sha2::{Digest, Sha256};
+
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    cover_image::{
+        CoverImage, CoverImageLoader, CoverImageStorage, UploadCoverImageErrorResponse,
+        UploadCoverImageResponse, upload_cover_image_mutation,
+    },
+    post::{Post, UserInputError, create_draft_mutation},
+};
+
+/// Signature and `IHDR` chunk of a 3 × 2 PNG, which is enough to detect its type and size
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02\x08\x06\0\0\0";
+
+/// Upload of `content`, named `filename`, which is written to `temp_dir` first
+fn upload(
+    temp_dir: &assert_fs::TempDir,
+    filename: &str,
+    content_type: &str,
+    content: &[u8],
+) -> UploadValue {
+    let file = temp_dir.child(filename);
+    file.write_binary(content).unwrap();
+
+    UploadValue {
+        filename: filename.to_string(),
+        content_type: Some(content_type.to_string()),
+        content: std::fs::File::open(file.path()).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn upload_cover_image_mutation_stores_file_by_content_hash() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let storage = CoverImageStorage {
+        directory: temp_dir.path().join("uploads"),
+        ..CoverImageStorage::default()
+    };
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Cover Draft", "Draft body.", None)
+        .await
+        .unwrap();
+
+    // act
+    let outcome = upload_cover_image_mutation(
+        &db_pool,
+        &storage,
+        id,
+        upload(&temp_dir, "cover.png", "image/png", PNG_HEADER),
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert!(matches!(
+        outcome,
+        UploadCoverImageResponse::UploadCoverImageSuccessResponse(_)
+    ));
+    let mut cover_images = CoverImageLoader::new(db_pool).load(&[id]).await.unwrap();
+    let CoverImage { url, width, height } = cover_images.remove(&id).unwrap();
+    assert_eq!((width, height), (3, 2));
+    let filename = format!("{:x}.png", Sha256::digest(PNG_HEADER));
+    assert_eq!(url, format!("/uploads/{filename}"));
+    assert_eq!(
+        std::fs::read(storage.directory.join(&filename)).unwrap(),
+        PNG_HEADER
+    );
+}
+
+#[tokio::test]
+async fn upload_cover_image_mutation_rejects_missing_post_large_and_unsupported_files() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let storage = CoverImageStorage {
+        directory: temp_dir.path().join("uploads"),
+        max_bytes: 64,
+    };
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Cover Draft", "Draft body.", None)
+        .await
+        .unwrap();
+
+    // act
+    let mut errors = Vec::new();
+    for (post_id, upload) in [
+        (
+            9_999,
+            upload(&temp_dir, "cover.png", "image/png", PNG_HEADER),
+        ),
+        (id, upload(&temp_dir, "large.png", "image/png", &[0; 65])),
+        (
+            id,
+            upload(&temp_dir, "notes.txt", "text/plain", b"not an image"),
+        ),
+    ] {
+        let outcome = upload_cover_image_mutation(&db_pool, &storage, post_id, upload)
+            .await
+            .unwrap();
+        let UploadCoverImageResponse::UploadCoverImageErrorResponse(
+            UploadCoverImageErrorResponse { error },
+        ) = outcome
+        else {
+            panic!("expected error response");
+        };
+        errors.push(error);
+    }
+
+    // assert
+    assert_eq!(
+        errors,
+        vec![
+            UserInputError {
+                field: String::from("postId"),
+                message: String::from("Did not find post with id `9999`"),
+                received: String::from("9999"),
+            },
+            UserInputError {
+                field: String::from("file"),
+                message: String::from("File is 65 bytes, which exceeds the limit of 64"),
+                received: String::from("large.png"),
+            },
+            UserInputError {
+                field: String::from("file"),
+                message: String::from("File should be a GIF, JPEG, PNG or WebP image"),
+                received: String::from("notes.txt"),
+            },
+        ]
+    );
+    let cover_images = CoverImageLoader::new(db_pool).load(&[id]).await.unwrap();
+    assert!(!cover_images.contains_key(&id));
+    assert!(!storage.directory.exists());
+}
@@ -1,5 +1,26 @@
+#[cfg(test)]
+mod comment;
+
+#[cfg(test)]
+mod cover_image;
+
 #[cfg(test)]
 mod post;
 
+#[cfg(test)]
+mod revision;
+
+#[cfg(test)]
+mod schedule;
+
+#[cfg(test)]
+mod search;
+
+#[cfg(test)]
+mod tag;
+
 #[cfg(test)]
 mod tests;
+
+#[cfg(test)]
+mod user;
@@ -1,8 +1,18 @@
 use crate::helpers::TestApp;
-use axum_graphql::model::post::{
-    DeleteDraftErrorResponse, DeleteDraftResponse, DeleteDraftSuccessResponse, Post,
-    UserInputError, create_draft_mutation, delete_draft_mutation, posts_query, publish_mutation,
+use axum_graphql::{
+    database::run_migrations,
+    model::post::{
+        DeleteDraftErrorResponse, DeleteDraftResponse, DeleteDraftSuccessResponse, Post,
+        PostCursor, PostFilter, PostPage, PostPageRequest, PublishErrorResponse, PublishResponse,
+        PublishSuccessResponse, RestoreDraftResponse, UnpublishErrorResponse, UnpublishResponse,
+        UnpublishSuccessResponse, UpdateDraftErrorResponse, UpdateDraftResponse,
+        UpdateDraftSuccessResponse, UserInputError, create_draft_mutation, delete_draft_mutation,
+        drafts_query, post_by_slug_query, post_query, posts_query, publish_mutation,
+        purge_deleted_drafts_mutation, restore_draft_mutation, trash_query, unpublish_mutation,
+        update_draft_mutation,
+    },
 };
+use chrono::{TimeDelta, Utc};
 use sqlx::sqlite::SqlitePoolOptions;
 
 #[tokio::test]
@@ -11,10 +21,23 @@ async fn posts_query_returns_expected_output_with_no_posts() {
     let db_pool = TestApp::get_db_pool().await;
 
     // act
-    let result = posts_query(&db_pool).await.unwrap();
+    let result = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest::default(),
+    )
+    .await
+    .unwrap();
 
     // assert
-    assert_eq!(result, Vec::<Post>::new());
+    assert_eq!(
+        result,
+        PostPage {
+            posts: Vec::<Post>::new(),
+            has_previous_page: false,
+            has_next_page: false
+        }
+    );
 }
 
 #[tokio::test]
@@ -23,24 +46,266 @@ async fn posts_query_returns_expected_output_with_posts() {
     let db_pool = TestApp::get_db_pool().await;
     let title = String::from("New Post Title");
     let body = String::from("# New Post\nNew post body");
-    let Post { id, .. } = create_draft_mutation(&db_pool, &title, &body)
+    let Post {
+        id,
+        slug,
+        created_at,
+        ..
+    } = create_draft_mutation(&db_pool, &title, &body, None)
         .await
         .unwrap();
-    let _ = publish_mutation(&db_pool, id).await;
+    let PublishResponse::PublishSuccessResponse(PublishSuccessResponse {
+        post: Post {
+            updated_at,
+            published_at,
+            ..
+        },
+    }) = publish_mutation(&db_pool, id).await.unwrap()
+    else {
+        panic!("draft should publish");
+    };
 
     // act
-    let result = posts_query(&db_pool).await.unwrap();
+    let result = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest::default(),
+    )
+    .await
+    .unwrap();
 
     // assert
+    assert!(published_at.is_some());
     assert_eq!(
         result,
-        vec![Post {
-            id,
-            title,
-            body,
-            published: true
-        }]
+        PostPage {
+            posts: vec![Post {
+                id,
+                title,
+                slug,
+                body,
+                published: true,
+                created_at,
+                updated_at,
+                published_at,
+                author_id: None,
+                deleted_at: None,
+                scheduled_at: None
+            }],
+            has_previous_page: false,
+            has_next_page: false
+        }
+    );
+}
+
+#[tokio::test]
+async fn posts_query_returns_newest_posts_first() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let mut ids = Vec::new();
+    for index in 0..3 {
+        let Post { id, .. } =
+            create_draft_mutation(&db_pool, &format!("Post {index}"), "Post body", None)
+                .await
+                .unwrap();
+        ids.push(id);
+    }
+    for id in [ids[1], ids[0], ids[2]] {
+        let _ = publish_mutation(&db_pool, id).await;
+
+        // publish timestamps have millisecond resolution
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    // act
+    let first_page = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            first: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let second_page = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            after: first_page.posts.last().map(PostCursor::from),
+            first: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        first_page
+            .posts
+            .iter()
+            .map(|post| post.id)
+            .collect::<Vec<_>>(),
+        vec![ids[2], ids[0]]
+    );
+    assert!(first_page.has_next_page);
+    assert_eq!(
+        second_page
+            .posts
+            .iter()
+            .map(|post| post.id)
+            .collect::<Vec<_>>(),
+        vec![ids[1]]
+    );
+    assert!(!second_page.has_next_page);
+}
+
+#[tokio::test]
+async fn drafts_query_pages_forwards_from_after_cursor() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let mut drafts = Vec::new();
+    for index in 0..5 {
+        let draft = create_draft_mutation(&db_pool, &format!("Draft {index}"), "Draft body", None)
+            .await
+            .unwrap();
+        drafts.push(draft);
+    }
+    let ids: Vec<i64> = drafts.iter().map(|draft| draft.id).collect();
+
+    // act
+    let result = drafts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            after: Some(PostCursor::from(&drafts[1])),
+            first: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        result.posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![ids[2], ids[3]]
+    );
+    assert!(result.has_previous_page);
+    assert!(result.has_next_page);
+}
+
+#[tokio::test]
+async fn drafts_query_pages_backwards_from_before_cursor() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let mut drafts = Vec::new();
+    for index in 0..5 {
+        let draft = create_draft_mutation(&db_pool, &format!("Draft {index}"), "Draft body", None)
+            .await
+            .unwrap();
+        drafts.push(draft);
+    }
+    let ids: Vec<i64> = drafts.iter().map(|draft| draft.id).collect();
+
+    // act
+    let result = drafts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            before: Some(PostCursor::from(&drafts[2])),
+            last: Some(5),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        result.posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![ids[0], ids[1]]
+    );
+    assert!(!result.has_previous_page);
+    assert!(result.has_next_page);
+}
+
+#[tokio::test]
+async fn create_draft_mutation_resolves_slug_collisions() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let title = "New Post Title";
+
+    // act
+    let first = create_draft_mutation(&db_pool, title, "First body", None)
+        .await
+        .unwrap();
+    let second = create_draft_mutation(&db_pool, title, "Second body", None)
+        .await
+        .unwrap();
+    let third = create_draft_mutation(&db_pool, "New Post Title!", "Third body", None)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(first.slug, "new-post-title");
+    assert_eq!(second.slug, "new-post-title-2");
+    assert_eq!(third.slug, "new-post-title-3");
+}
+
+#[tokio::test]
+async fn create_draft_mutation_resolves_concurrent_slug_collisions() {
+    // arrange
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let database_url = format!(
+        "sqlite://{}?mode=rwc",
+        temp_dir.path().join("sqlite.db").display()
     );
+    let db_pool = SqlitePoolOptions::new()
+        .max_connections(8)
+        .connect(&database_url)
+        .await
+        .unwrap();
+    run_migrations(&db_pool).await;
+
+    // act
+    let outcomes = futures::future::join_all(
+        (0..8).map(|_| create_draft_mutation(&db_pool, "Concurrent Title", "Draft body", None)),
+    )
+    .await;
+
+    // assert
+    let mut slugs: Vec<_> = outcomes
+        .into_iter()
+        .map(|outcome| outcome.unwrap().slug)
+        .collect();
+    slugs.sort();
+    slugs.dedup();
+    assert_eq!(slugs.len(), 8);
+}
+
+#[tokio::test]
+async fn post_queries_return_post_matching_id_or_slug() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let post = create_draft_mutation(&db_pool, "New Post Title", "New post body", None)
+        .await
+        .unwrap();
+
+    // act
+    let by_id = post_query(&db_pool, post.id).await.unwrap();
+    let by_slug = post_by_slug_query(&db_pool, "new-post-title")
+        .await
+        .unwrap();
+    let missing = post_by_slug_query(&db_pool, "missing-post-title")
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(by_id.as_ref(), Some(&post));
+    assert_eq!(by_slug, Some(post));
+    assert_eq!(missing, None);
 }
 
 #[tokio::test]
@@ -56,7 +321,7 @@ async fn create_draft_mutation_fails_if_db_is_not_initialised() {
         .unwrap();
 
     // act
-    let outcome = create_draft_mutation(&db_pool, "Draft Post Title", "Draft Post Body")
+    let outcome = create_draft_mutation(&db_pool, "Draft Post Title", "Draft Post Body", None)
         .await
         .unwrap_err();
 
@@ -80,13 +345,89 @@ async fn create_draft_mutation_fails_if_db_is_not_initialised() {
     assert_eq!(chain.next().map(|val| format!("{val}")), None);
 }
 
+#[tokio::test]
+async fn update_draft_mutation_returns_draft_on_valid_input() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post {
+        id,
+        slug,
+        created_at,
+        updated_at: created_updated_at,
+        ..
+    } = create_draft_mutation(&db_pool, "New Post Title", "New post body", None)
+        .await
+        .unwrap();
+    let title = String::from("Updated Post Title");
+    let body = String::from("# Updated Post\nUpdated post body");
+
+    // act
+    let outcome = update_draft_mutation(&db_pool, id, &title, &body)
+        .await
+        .unwrap();
+
+    // assert
+    let UpdateDraftResponse::UpdateDraftSuccessResponse(UpdateDraftSuccessResponse {
+        post: Post { updated_at, .. },
+    }) = outcome
+    else {
+        panic!("draft should update");
+    };
+    assert!(updated_at >= created_updated_at);
+    assert_eq!(
+        outcome,
+        UpdateDraftResponse::UpdateDraftSuccessResponse(UpdateDraftSuccessResponse {
+            post: Post {
+                id,
+                title,
+                slug,
+                body,
+                published: false,
+                created_at,
+                updated_at,
+                published_at: None,
+                author_id: None,
+                deleted_at: None,
+                scheduled_at: None
+            },
+        })
+    );
+}
+
+#[tokio::test]
+async fn update_draft_mutation_returns_error_message_if_post_is_published() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "New Post Title", "New post body", None)
+        .await
+        .unwrap();
+    let _ = publish_mutation(&db_pool, id).await;
+
+    // act
+    let outcome = update_draft_mutation(&db_pool, id, "Updated Post Title", "Updated body")
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(
+        outcome,
+        UpdateDraftResponse::UpdateDraftErrorResponse(UpdateDraftErrorResponse {
+            error: UserInputError {
+                field: String::from("id"),
+                message: format!("Did not find draft post with id `{id}`"),
+                received: id.to_string()
+            }
+        })
+    );
+}
+
 #[tokio::test]
 async fn delete_draft_mutation_returns_error_message_if_draft_does_not_exist() {
     // arrange
     let db_pool = TestApp::get_db_pool().await;
     let title = String::from("New Post Title");
     let body = String::from("# New Post\nNew post body");
-    let Post { id, .. } = create_draft_mutation(&db_pool, &title, &body)
+    let Post { id, .. } = create_draft_mutation(&db_pool, &title, &body, None)
         .await
         .unwrap();
     let _ = publish_mutation(&db_pool, id).await;
@@ -113,7 +454,13 @@ async fn delete_draft_mutation_returns_draft_on_valid_input() {
     let db_pool = TestApp::get_db_pool().await;
     let title = String::from("New Post Title");
     let body = String::from("# New Post\nNew post body");
-    let Post { id, .. } = create_draft_mutation(&db_pool, &title, &body)
+    let Post {
+        id,
+        slug,
+        created_at,
+        updated_at,
+        ..
+    } = create_draft_mutation(&db_pool, &title, &body, None)
         .await
         .unwrap();
 
@@ -121,14 +468,26 @@ async fn delete_draft_mutation_returns_draft_on_valid_input() {
     let outcome = delete_draft_mutation(&db_pool, id).await.unwrap();
 
     // assert
+    let deleted_at = match &outcome {
+        DeleteDraftResponse::DeleteDraftSuccessResponse(value) => value.post.deleted_at,
+        DeleteDraftResponse::DeleteDraftErrorResponse(_) => None,
+    };
+    assert!(deleted_at.is_some());
     assert_eq!(
         outcome,
         DeleteDraftResponse::DeleteDraftSuccessResponse(DeleteDraftSuccessResponse {
             post: Post {
                 id,
                 title,
+                slug,
                 body,
-                published: false
+                published: false,
+                created_at,
+                updated_at,
+                published_at: None,
+                author_id: None,
+                deleted_at,
+                scheduled_at: None
             },
         })
     );
@@ -168,6 +527,101 @@ async fn delete_draft_mutation_fails_if_db_is_not_initialised() {
     assert_eq!(chain.next().map(|val| format!("{val}")), None);
 }
 
+#[tokio::test]
+async fn publish_mutation_returns_error_message_if_post_is_already_published() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "New Post Title", "New post body", None)
+        .await
+        .unwrap();
+    let _ = publish_mutation(&db_pool, id).await;
+
+    // act
+    let outcome = publish_mutation(&db_pool, id).await.unwrap();
+
+    // assert
+    assert_eq!(
+        outcome,
+        PublishResponse::PublishErrorResponse(PublishErrorResponse {
+            error: UserInputError {
+                field: String::from("id"),
+                message: format!("Post with id `{id}` is already published"),
+                received: id.to_string()
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn unpublish_mutation_returns_draft_on_valid_input() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let title = String::from("New Post Title");
+    let body = String::from("# New Post\nNew post body");
+    let Post {
+        id,
+        slug,
+        created_at,
+        ..
+    } = create_draft_mutation(&db_pool, &title, &body, None)
+        .await
+        .unwrap();
+    let _ = publish_mutation(&db_pool, id).await;
+
+    // act
+    let outcome = unpublish_mutation(&db_pool, id).await.unwrap();
+
+    // assert
+    let UnpublishResponse::UnpublishSuccessResponse(UnpublishSuccessResponse {
+        post: Post { updated_at, .. },
+    }) = outcome
+    else {
+        panic!("post should unpublish");
+    };
+    assert_eq!(
+        outcome,
+        UnpublishResponse::UnpublishSuccessResponse(UnpublishSuccessResponse {
+            post: Post {
+                id,
+                title,
+                slug,
+                body,
+                published: false,
+                created_at,
+                updated_at,
+                published_at: None,
+                author_id: None,
+                deleted_at: None,
+                scheduled_at: None
+            },
+        })
+    );
+}
+
+#[tokio::test]
+async fn unpublish_mutation_returns_error_message_if_post_is_not_published() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "New Post Title", "New post body", None)
+        .await
+        .unwrap();
+
+    // act
+    let outcome = unpublish_mutation(&db_pool, id).await.unwrap();
+
+    // assert
+    assert_eq!(
+        outcome,
+        UnpublishResponse::UnpublishErrorResponse(UnpublishErrorResponse {
+            error: UserInputError {
+                field: String::from("id"),
+                message: format!("Post with id `{id}` is not published"),
+                received: id.to_string()
+            }
+        })
+    );
+}
+
 #[tokio::test]
 async fn publish_mutation_fails_if_db_is_not_initialised() {
     // arrange
@@ -201,3 +655,102 @@ async fn publish_mutation_fails_if_db_is_not_initialised() {
     );
     assert_eq!(chain.next().map(|val| format!("{val}")), None);
 }
+
+#[tokio::test]
+async fn delete_draft_mutation_moves_draft_to_trash() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, slug, .. } =
+        create_draft_mutation(&db_pool, "Deleted Draft", "Draft body.", None)
+            .await
+            .unwrap();
+
+    // act
+    delete_draft_mutation(&db_pool, id).await.unwrap();
+
+    // assert
+    let drafts = drafts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(drafts.posts, Vec::<Post>::new());
+    assert_eq!(post_query(&db_pool, id).await.unwrap(), None);
+    assert_eq!(post_by_slug_query(&db_pool, &slug).await.unwrap(), None);
+    let trash = trash_query(&db_pool).await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].id, id);
+    assert!(trash[0].deleted_at.is_some());
+    let UpdateDraftResponse::UpdateDraftErrorResponse(_) =
+        update_draft_mutation(&db_pool, id, "New Title", "New body.")
+            .await
+            .unwrap()
+    else {
+        panic!("expected error response");
+    };
+}
+
+#[tokio::test]
+async fn restore_draft_mutation_moves_draft_out_of_trash() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Deleted Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    delete_draft_mutation(&db_pool, id).await.unwrap();
+
+    // act
+    let outcome = restore_draft_mutation(&db_pool, id).await.unwrap();
+    let repeated_outcome = restore_draft_mutation(&db_pool, id).await.unwrap();
+
+    // assert
+    let RestoreDraftResponse::RestoreDraftSuccessResponse(outcome) = outcome else {
+        panic!("expected success response");
+    };
+    assert_eq!(outcome.post.id, id);
+    assert_eq!(outcome.post.deleted_at, None);
+    assert_eq!(trash_query(&db_pool).await.unwrap(), Vec::<Post>::new());
+    assert_eq!(post_query(&db_pool, id).await.unwrap(), Some(outcome.post));
+    let RestoreDraftResponse::RestoreDraftErrorResponse(repeated_outcome) = repeated_outcome else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        repeated_outcome.error,
+        UserInputError {
+            field: String::from("id"),
+            message: format!("Did not find deleted draft with id `{id}`"),
+            received: id.to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn purge_deleted_drafts_mutation_only_removes_drafts_deleted_before_cutoff() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id: deleted_id, .. } =
+        create_draft_mutation(&db_pool, "Deleted Draft", "Draft body.", None)
+            .await
+            .unwrap();
+    let Post { id: kept_id, .. } =
+        create_draft_mutation(&db_pool, "Kept Draft", "Draft body.", None)
+            .await
+            .unwrap();
+    delete_draft_mutation(&db_pool, deleted_id).await.unwrap();
+
+    // act
+    let early_count = purge_deleted_drafts_mutation(&db_pool, Utc::now() - TimeDelta::hours(1))
+        .await
+        .unwrap();
+    let count = purge_deleted_drafts_mutation(&db_pool, Utc::now() + TimeDelta::seconds(1))
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(early_count, 0);
+    assert_eq!(count, 1);
+    assert_eq!(trash_query(&db_pool).await.unwrap(), Vec::<Post>::new());
+    assert!(post_query(&db_pool, kept_id).await.unwrap().is_some());
+}
@@ -0,0 +1,147 @@
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    post::{
+        Post, UserInputError, create_draft_mutation, delete_draft_mutation, update_draft_mutation,
+    },
+    revision::{
+        PostRevision, RestoreRevisionResponse, post_revisions_query, restore_revision_mutation,
+    },
+};
+
+#[tokio::test]
+async fn post_revisions_query_returns_snapshot_for_each_content_change() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "First Title", "First body.", None)
+        .await
+        .unwrap();
+
+    // act
+    update_draft_mutation(&db_pool, id, "Second Title", "First body.")
+        .await
+        .unwrap();
+    update_draft_mutation(&db_pool, id, "Second Title", "First body.")
+        .await
+        .unwrap();
+    update_draft_mutation(&db_pool, id, "Second Title", "Second body.")
+        .await
+        .unwrap();
+
+    // assert
+    let revisions = post_revisions_query(&db_pool, id).await.unwrap();
+    assert_eq!(
+        revisions
+            .iter()
+            .map(|revision| (revision.title.as_str(), revision.body.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("Second Title", "Second body."),
+            ("Second Title", "First body."),
+            ("First Title", "First body."),
+        ]
+    );
+    assert!(revisions.iter().all(|revision| revision.post_id == id));
+}
+
+#[tokio::test]
+async fn restore_revision_mutation_restores_content_and_records_revision() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "First Title", "First body.", None)
+        .await
+        .unwrap();
+    update_draft_mutation(&db_pool, id, "Second Title", "Second body.")
+        .await
+        .unwrap();
+    let revisions = post_revisions_query(&db_pool, id).await.unwrap();
+    let PostRevision {
+        id: first_revision_id,
+        ..
+    } = revisions[1];
+
+    // act
+    let outcome = restore_revision_mutation(&db_pool, id, first_revision_id)
+        .await
+        .unwrap();
+
+    // assert
+    let RestoreRevisionResponse::RestoreRevisionSuccessResponse(outcome) = outcome else {
+        panic!("expected success response");
+    };
+    assert_eq!(outcome.post.title, "First Title");
+    assert_eq!(outcome.post.body, "First body.");
+    let revisions = post_revisions_query(&db_pool, id).await.unwrap();
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[0].title, "First Title");
+}
+
+#[tokio::test]
+async fn restore_revision_mutation_returns_user_error_for_revision_of_other_post() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id: first_id, .. } =
+        create_draft_mutation(&db_pool, "First Draft", "Draft body.", None)
+            .await
+            .unwrap();
+    let Post { id: second_id, .. } =
+        create_draft_mutation(&db_pool, "Second Draft", "Draft body.", None)
+            .await
+            .unwrap();
+    let revisions = post_revisions_query(&db_pool, second_id).await.unwrap();
+    let revision_id = revisions[0].id;
+
+    // act
+    let outcome = restore_revision_mutation(&db_pool, first_id, revision_id)
+        .await
+        .unwrap();
+
+    // assert
+    let RestoreRevisionResponse::RestoreRevisionErrorResponse(outcome) = outcome else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        outcome.error,
+        UserInputError {
+            field: String::from("revisionId"),
+            message: format!(
+                "Did not find revision with id `{revision_id}` for post with id `{first_id}`"
+            ),
+            received: revision_id.to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn restore_revision_mutation_returns_user_error_for_deleted_draft() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "First Title", "First body.", None)
+        .await
+        .unwrap();
+    update_draft_mutation(&db_pool, id, "Second Title", "Second body.")
+        .await
+        .unwrap();
+    let revisions = post_revisions_query(&db_pool, id).await.unwrap();
+    let revision_id = revisions[1].id;
+    delete_draft_mutation(&db_pool, id).await.unwrap();
+
+    // act
+    let outcome = restore_revision_mutation(&db_pool, id, revision_id)
+        .await
+        .unwrap();
+
+    // assert
+    let RestoreRevisionResponse::RestoreRevisionErrorResponse(outcome) = outcome else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        outcome.error,
+        UserInputError {
+            field: String::from("postId"),
+            message: format!("Did not find post with id `{id}`"),
+            received: id.to_string(),
+        }
+    );
+    let revisions = post_revisions_query(&db_pool, id).await.unwrap();
+    assert_eq!(revisions.len(), 2);
+}
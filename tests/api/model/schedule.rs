@@ -0,0 +1,201 @@
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    post::{
+        Post, PostCursor, PostFilter, PostPageRequest, UserInputError, create_draft_mutation,
+        delete_draft_mutation, post_query, posts_query, publish_mutation,
+    },
+    schedule::{
+        CancelScheduledPublishResponse, SchedulePublishResponse, cancel_scheduled_publish_mutation,
+        publish_due_posts_mutation, schedule_publish_mutation,
+    },
+};
+use chrono::{SubsecRound, TimeDelta, Utc};
+
+#[tokio::test]
+async fn schedule_publish_mutation_sets_scheduled_time_of_draft() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Scheduled Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    let at = Utc::now() + TimeDelta::days(1);
+
+    // act
+    let outcome = schedule_publish_mutation(&db_pool, id, at).await.unwrap();
+
+    // assert
+    let SchedulePublishResponse::SchedulePublishSuccessResponse(outcome) = outcome else {
+        panic!("expected success response");
+    };
+    // timestamps are stored to the nearest millisecond
+    assert_eq!(outcome.post.scheduled_at, Some(at.round_subsecs(3)));
+    assert!(!outcome.post.published);
+}
+
+#[tokio::test]
+async fn schedule_publish_mutation_returns_user_error_for_past_time_or_published_post() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Published Post", "Post body.", None)
+        .await
+        .unwrap();
+    publish_mutation(&db_pool, id).await.unwrap();
+    let past = Utc::now() - TimeDelta::minutes(1);
+
+    // act
+    let past_outcome = schedule_publish_mutation(&db_pool, id, past).await.unwrap();
+    let published_outcome =
+        schedule_publish_mutation(&db_pool, id, Utc::now() + TimeDelta::days(1))
+            .await
+            .unwrap();
+
+    // assert
+    let SchedulePublishResponse::SchedulePublishErrorResponse(past_outcome) = past_outcome else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        past_outcome.error,
+        UserInputError {
+            field: String::from("at"),
+            message: String::from("Scheduled publish time should be in the future"),
+            received: past.to_rfc3339(),
+        }
+    );
+    let SchedulePublishResponse::SchedulePublishErrorResponse(published_outcome) =
+        published_outcome
+    else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        published_outcome.error.message,
+        format!("Post with id `{id}` is already published")
+    );
+}
+
+#[tokio::test]
+async fn cancel_scheduled_publish_mutation_clears_scheduled_time() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Scheduled Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    schedule_publish_mutation(&db_pool, id, Utc::now() + TimeDelta::days(1))
+        .await
+        .unwrap();
+
+    // act
+    let outcome = cancel_scheduled_publish_mutation(&db_pool, id)
+        .await
+        .unwrap();
+    let repeated_outcome = cancel_scheduled_publish_mutation(&db_pool, id)
+        .await
+        .unwrap();
+
+    // assert
+    let CancelScheduledPublishResponse::CancelScheduledPublishSuccessResponse(outcome) = outcome
+    else {
+        panic!("expected success response");
+    };
+    assert_eq!(outcome.post.scheduled_at, None);
+    let CancelScheduledPublishResponse::CancelScheduledPublishErrorResponse(repeated_outcome) =
+        repeated_outcome
+    else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        repeated_outcome.error,
+        UserInputError {
+            field: String::from("id"),
+            message: format!("Draft post with id `{id}` is not scheduled for publishing"),
+            received: id.to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn publish_due_posts_mutation_only_publishes_due_drafts() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let mut ids = Vec::new();
+    for title in ["Due Draft", "Later Draft", "Deleted Draft"] {
+        let Post { id, .. } = create_draft_mutation(&db_pool, title, "Draft body.", None)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let (due_at, later_at) = (
+        Utc::now() + TimeDelta::hours(1),
+        Utc::now() + TimeDelta::hours(3),
+    );
+    for (id, at) in [(ids[0], due_at), (ids[1], later_at), (ids[2], due_at)] {
+        schedule_publish_mutation(&db_pool, id, at).await.unwrap();
+    }
+    delete_draft_mutation(&db_pool, ids[2]).await.unwrap();
+
+    // act
+    let published = publish_due_posts_mutation(&db_pool, Utc::now() + TimeDelta::hours(2))
+        .await
+        .unwrap();
+
+    // assert
+    let [post] = published.as_slice() else {
+        panic!("expected a single published post");
+    };
+    assert_eq!(post.id, ids[0]);
+    assert!(post.published);
+    assert_eq!(post.published_at, Some(due_at.round_subsecs(3)));
+    assert_eq!(post.scheduled_at, None);
+    let later = post_query(&db_pool, ids[1]).await.unwrap().unwrap();
+    assert!(!later.published);
+    assert_eq!(later.scheduled_at, Some(later_at.round_subsecs(3)));
+}
+
+#[tokio::test]
+async fn scheduled_posts_are_paged_in_order_with_other_published_posts() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let mut ids = Vec::new();
+    for title in ["Scheduled Draft", "Published Draft"] {
+        let Post { id, .. } = create_draft_mutation(&db_pool, title, "Draft body.", None)
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    schedule_publish_mutation(&db_pool, ids[0], Utc::now() + TimeDelta::hours(1))
+        .await
+        .unwrap();
+    publish_due_posts_mutation(&db_pool, Utc::now() + TimeDelta::hours(2))
+        .await
+        .unwrap();
+    publish_mutation(&db_pool, ids[1]).await.unwrap();
+
+    // act
+    let first_page = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            first: Some(1),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let second_page = posts_query(
+        &db_pool,
+        &PostFilter::default(),
+        &PostPageRequest {
+            after: first_page.posts.last().map(PostCursor::from),
+            first: Some(1),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        [first_page.posts[0].id, second_page.posts[0].id],
+        [ids[0], ids[1]]
+    );
+    assert!(!second_page.has_next_page);
+}
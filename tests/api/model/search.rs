@@ -0,0 +1,134 @@
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    post::{Post, create_draft_mutation, publish_mutation, update_draft_mutation},
+    search::search_posts_query,
+};
+
+#[tokio::test]
+async fn search_posts_query_returns_highlighted_published_matches() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(
+        &db_pool,
+        "Running Rust",
+        "Notes on running a GraphQL API in production.",
+        None,
+    )
+    .await
+    .unwrap();
+    let _ = publish_mutation(&db_pool, id).await;
+    let _ = create_draft_mutation(&db_pool, "Draft Title", "Still running drafts.", None)
+        .await
+        .unwrap();
+
+    // act
+    let outcome = search_posts_query(&db_pool, "runs", false, 0, None)
+        .await
+        .unwrap();
+
+    // assert
+    assert!(!outcome.has_next_page);
+    assert_eq!(outcome.results.len(), 1);
+    let result = &outcome.results[0];
+    assert_eq!(result.post.id, id);
+    assert_eq!(result.title_highlight, "<mark>Running</mark> Rust");
+    assert_eq!(
+        result.snippet,
+        "Notes on <mark>running</mark> a GraphQL API in production."
+    );
+}
+
+#[tokio::test]
+async fn search_posts_query_escapes_html_in_highlights() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(
+        &db_pool,
+        "<script>alert(1)</script> Rust",
+        "Rust & <img src=x onerror=alert(1)>",
+        None,
+    )
+    .await
+    .unwrap();
+    let _ = publish_mutation(&db_pool, id).await;
+
+    // act
+    let outcome = search_posts_query(&db_pool, "rust", false, 0, None)
+        .await
+        .unwrap();
+
+    // assert
+    let result = &outcome.results[0];
+    assert_eq!(
+        result.title_highlight,
+        "&lt;script&gt;alert(1)&lt;/script&gt; <mark>Rust</mark>"
+    );
+    assert_eq!(
+        result.snippet,
+        "<mark>Rust</mark> &amp; &lt;img src=x onerror=alert(1)&gt;"
+    );
+}
+
+#[tokio::test]
+async fn search_posts_query_includes_drafts_only_when_requested() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Draft Title", "Draft body text.", None)
+        .await
+        .unwrap();
+
+    // act
+    let without_drafts = search_posts_query(&db_pool, "draft", false, 0, None)
+        .await
+        .unwrap();
+    let with_drafts = search_posts_query(&db_pool, "draft", true, 0, None)
+        .await
+        .unwrap();
+
+    // assert
+    assert!(without_drafts.results.is_empty());
+    assert_eq!(
+        with_drafts
+            .results
+            .iter()
+            .map(|result| result.post.id)
+            .collect::<Vec<_>>(),
+        vec![id]
+    );
+}
+
+#[tokio::test]
+async fn search_posts_query_reflects_updated_drafts() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Draft Title", "Original wording.", None)
+        .await
+        .unwrap();
+    let _ = update_draft_mutation(&db_pool, id, "Draft Title", "Replacement wording.")
+        .await
+        .unwrap();
+
+    // act
+    let original = search_posts_query(&db_pool, "original", true, 0, None)
+        .await
+        .unwrap();
+    let replacement = search_posts_query(&db_pool, "replacement", true, 0, None)
+        .await
+        .unwrap();
+
+    // assert
+    assert!(original.results.is_empty());
+    assert_eq!(replacement.results.len(), 1);
+}
+
+#[tokio::test]
+async fn search_posts_query_treats_fts_syntax_as_plain_text() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+
+    // act
+    let outcome = search_posts_query(&db_pool, "\"unbalanced AND (", true, 0, None).await;
+
+    // assert
+    assert!(outcome.unwrap().results.is_empty());
+}
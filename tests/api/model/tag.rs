@@ -0,0 +1,155 @@
+use async_graphql::dataloader::Loader;
+
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    post::{
+        Post, PostFilter, PostPage, PostPageRequest, UserInputError, create_draft_mutation,
+        drafts_query,
+    },
+    tag::{
+        AddTagResponse, RemoveTagErrorResponse, RemoveTagResponse, Tag, TagsLoader,
+        add_tag_mutation, remove_tag_mutation,
+    },
+};
+
+#[tokio::test]
+async fn add_tag_mutation_normalises_name_and_reuses_existing_tags() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id: first_id, .. } =
+        create_draft_mutation(&db_pool, "First Draft", "Draft body.", None)
+            .await
+            .unwrap();
+    let Post { id: second_id, .. } =
+        create_draft_mutation(&db_pool, "Second Draft", "Draft body.", None)
+            .await
+            .unwrap();
+
+    // act
+    for (post_id, name) in [
+        (first_id, "Rust Lang"),
+        (first_id, "rust-lang"),
+        (first_id, "GraphQL"),
+        (second_id, "  RUST lang!"),
+    ] {
+        let outcome = add_tag_mutation(&db_pool, post_id, name).await.unwrap();
+        assert!(matches!(outcome, AddTagResponse::AddTagSuccessResponse(_)));
+    }
+
+    // assert
+    let mut tags = TagsLoader::new(db_pool)
+        .load(&[first_id, second_id])
+        .await
+        .unwrap();
+    let first_tags = tags.remove(&first_id).unwrap();
+    let second_tags = tags.remove(&second_id).unwrap();
+    assert_eq!(
+        first_tags
+            .iter()
+            .map(|tag| tag.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["graphql", "rust-lang"]
+    );
+    let [
+        Tag {
+            id: second_tag_id, ..
+        },
+    ] = second_tags.as_slice()
+    else {
+        panic!("expected a single tag");
+    };
+    assert_eq!(first_tags[1].id, *second_tag_id);
+}
+
+#[tokio::test]
+async fn add_tag_mutation_returns_user_error_for_invalid_name_or_missing_post() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Tagged Draft", "Draft body.", None)
+        .await
+        .unwrap();
+
+    // act
+    let invalid_name = add_tag_mutation(&db_pool, id, "!!!").await.unwrap();
+    let missing_post = add_tag_mutation(&db_pool, 9_999, "rust").await.unwrap();
+
+    // assert
+    let AddTagResponse::AddTagErrorResponse(invalid_name) = invalid_name else {
+        panic!("expected error response");
+    };
+    assert_eq!(
+        invalid_name.error,
+        UserInputError {
+            field: String::from("name"),
+            message: String::from("Tag name should include at least one ASCII letter or digit"),
+            received: String::from("!!!"),
+        }
+    );
+    let AddTagResponse::AddTagErrorResponse(missing_post) = missing_post else {
+        panic!("expected error response");
+    };
+    assert_eq!(missing_post.error.field, "postId");
+}
+
+#[tokio::test]
+async fn remove_tag_mutation_removes_tag_or_returns_user_error() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Tagged Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    add_tag_mutation(&db_pool, id, "rust").await.unwrap();
+
+    // act
+    let removed = remove_tag_mutation(&db_pool, id, "Rust").await.unwrap();
+    let not_tagged = remove_tag_mutation(&db_pool, id, "rust").await.unwrap();
+
+    // assert
+    assert!(matches!(
+        removed,
+        RemoveTagResponse::RemoveTagSuccessResponse(_)
+    ));
+    assert_eq!(
+        not_tagged,
+        RemoveTagResponse::RemoveTagErrorResponse(RemoveTagErrorResponse {
+            error: UserInputError {
+                field: String::from("name"),
+                message: format!("Post with id `{id}` is not tagged `rust`"),
+                received: String::from("rust"),
+            }
+        })
+    );
+    let tags = TagsLoader::new(db_pool).load(&[id]).await.unwrap();
+    assert!(!tags.contains_key(&id));
+}
+
+#[tokio::test]
+async fn drafts_query_filters_by_tag() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Tagged Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    let _ = create_draft_mutation(&db_pool, "Untagged Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    add_tag_mutation(&db_pool, id, "rust").await.unwrap();
+
+    // act
+    let PostPage { posts, .. } = drafts_query(
+        &db_pool,
+        &PostFilter {
+            tag: Some(String::from("rust")),
+            ..PostFilter::default()
+        },
+        &PostPageRequest::default(),
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(
+        posts.iter().map(|post| post.id).collect::<Vec<_>>(),
+        vec![id]
+    );
+}
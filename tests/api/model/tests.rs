@@ -4,7 +4,10 @@ use axum::{
     body::Body,
     http::{Method, Request, StatusCode, header},
 };
-use axum_graphql::startup::ApplicationRouter;
+use axum_graphql::{
+    model::limits::QueryLimits,
+    startup::{ApplicationConfig, ApplicationRouter},
+};
 use futures::executor::block_on;
 use http_body_util::BodyExt;
 use serde_json::{Value, json};
@@ -22,6 +25,8 @@ mod helpers {
     use serde_json::{Value, json};
     use tower::{Service, ServiceExt};
 
+    use crate::helpers::TestApp;
+
     pub async fn create_draft(app: &mut Router, title: &str, body: &str) -> i64 {
         let create_draft_json_request_body: Value = json!({
             "operationName":"CreateDraftMutation",
@@ -38,6 +43,7 @@ mod helpers {
             .method(Method::POST)
             .uri("/")
             .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .header(header::AUTHORIZATION, TestApp::authorization_header())
             .body(Body::from(create_draft_json_request_body.to_string()))
             .unwrap();
         let response = ServiceExt::<Request<Body>>::ready(app)
@@ -53,6 +59,62 @@ mod helpers {
         id.as_i64().unwrap()
     }
 
+    /// Send GraphQL `request_body` to `app`, with `authorization` as the `Authorization` header,
+    /// if set, returning the JSON response body
+    pub async fn send(
+        app: &mut Router,
+        authorization: Option<String>,
+        request_body: &Value,
+    ) -> Value {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref());
+        if let Some(value) = authorization {
+            request = request.header(header::AUTHORIZATION, value);
+        }
+        let response = ServiceExt::<Request<Body>>::ready(app)
+            .await
+            .unwrap()
+            .call(request.body(Body::from(request_body.to_string())).unwrap())
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    pub async fn create_user(app: &mut Router, name: &str) -> i64 {
+        let create_user_json_request_body: Value = json!({
+            "operationName":"CreateUserMutation",
+            "variables":{},
+            "query": format!(r#"mutation CreateUserMutation {{
+                 createUser(name: "{name}") {{
+                     id
+                 }}
+            }}"#),
+        });
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .header(header::AUTHORIZATION, TestApp::authorization_header())
+            .body(Body::from(create_user_json_request_body.to_string()))
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(app)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json_data: Value = serde_json::from_slice(&body).unwrap();
+        let id = &json_data["data"]["createUser"]["id"];
+
+        id.as_i64().unwrap()
+    }
+
     pub async fn publish_draft(app: &mut Router, id: i64) {
         let publish_draft_json_request_body: Value = json!({
             "operationName":"PublishMutation",
@@ -81,6 +143,7 @@ mod helpers {
             .method(Method::POST)
             .uri("/")
             .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .header(header::AUTHORIZATION, TestApp::authorization_header())
             .body(Body::from(publish_draft_json_request_body.to_string()))
             .unwrap();
         let _response = ServiceExt::<Request<Body>>::ready(app)
@@ -112,6 +175,7 @@ async fn snapshot_graqphql_query_async<P: AsRef<Path>>(path: P) {
                 .method(Method::POST)
                 .uri("/")
                 .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
                 .body(Body::from(json_request_body.to_string()))
                 .unwrap(),
         )
@@ -180,7 +244,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
     let drafts_json_request_body: Value = json!({
         "operationName":"DraftsQuery",
         "variables":{},
-        "query":"query DraftsQuery { drafts { id title} }"
+        "query":"query DraftsQuery { drafts { nodes { id title } } }"
     });
 
     // act
@@ -188,6 +252,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
         .method(Method::POST)
         .uri("/")
         .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .header(header::AUTHORIZATION, TestApp::authorization_header())
         .body(Body::from(drafts_json_request_body.to_string()))
         .unwrap();
     let response = ServiceExt::<Request<Body>>::ready(&mut router)
@@ -205,7 +270,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
     assert_eq!(
         body,
         json!({
-            "data": { "drafts": [] },
+            "data": { "drafts": { "nodes": [] } },
             "extensions": { "traceId": "00000000000000000000000000000000" }
         })
     );
@@ -227,6 +292,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
         .method(Method::POST)
         .uri("/")
         .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .header(header::AUTHORIZATION, TestApp::authorization_header())
         .body(Body::from(create_draft_json_request_body.to_string()))
         .unwrap();
     let response = ServiceExt::<Request<Body>>::ready(&mut router)
@@ -254,6 +320,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
         .method(Method::POST)
         .uri("/")
         .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .header(header::AUTHORIZATION, TestApp::authorization_header())
         .body(Body::from(drafts_json_request_body.to_string()))
         .unwrap();
     let response = ServiceExt::<Request<Body>>::ready(&mut router)
@@ -271,7 +338,7 @@ async fn graphql_endpoint_responds_to_drafts_query() {
     assert_eq!(
         body,
         json!({
-            "data": { "drafts": [ { "id": 1, "title": "Draft title" } ] },
+            "data": { "drafts": { "nodes": [ { "id": 1, "title": "Draft title" } ] } },
             "extensions": { "traceId": "00000000000000000000000000000000" }
         })
     );
@@ -284,7 +351,7 @@ async fn posts_return_empty_array_when_no_posts_exist() {
     let posts_json_request_body: Value = json!({
         "operationName":"PostsQuery",
         "variables":{},
-        "query":"query PostsQuery { posts { id title} }"
+        "query":"query PostsQuery { posts { nodes { id title } } }"
     });
 
     // act
@@ -308,7 +375,7 @@ async fn posts_return_empty_array_when_no_posts_exist() {
     assert_eq!(
         body,
         json!({
-            "data": { "posts": [] },
+            "data": { "posts": { "nodes": [] } },
             "extensions": { "traceId": "00000000000000000000000000000000" }
         })
     );
@@ -326,7 +393,7 @@ async fn posts_returns_existing_posts() {
     let posts_json_request_body: Value = json!({
         "operationName":"PostsQuery",
         "variables":{},
-        "query":"query PostsQuery { posts { id title} }"
+        "query":"query PostsQuery { posts { nodes { id title } } }"
     });
 
     // act
@@ -351,41 +418,117 @@ async fn posts_returns_existing_posts() {
     assert_eq!(
         body,
         json!({
-            "data": { "posts": [
-                { "id": id_1, "title": "First Post Title" },
+            "data": { "posts": { "nodes": [
                 { "id": id_3, "title": "Third Post Title" },
-            ]},
+                { "id": id_1, "title": "First Post Title" },
+            ]}},
             "extensions": { "traceId": "00000000000000000000000000000000" }
         })
     );
 }
 
 #[tokio::test]
-async fn publish_returns_user_error_for_invalid_id() {
+async fn posts_pages_through_existing_posts_with_cursors() {
     // arrange
-    let ApplicationRouter { router } = TestApp::spawn_routers().await;
-    let id = 9_999;
-    let publish_draft_json_request_body: Value = json!({
-        "operationName":"PublishMutation",
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    for index in 1..=3 {
+        let id =
+            helpers::create_draft(&mut router, &format!("Post {index} Title"), "Post body.").await;
+        helpers::publish_draft(&mut router, id).await;
+    }
+    let query = "query PostsQuery($after: String) {
+  posts(first: 2, after: $after) {
+    edges { cursor node { title } }
+    pageInfo { hasNextPage endCursor }
+  }
+}";
+    let first_page_json_request_body: Value = json!({
+        "operationName":"PostsQuery",
         "variables":{},
-        "query": format!("mutation PublishMutation {{
-  publish(id: {id}) {{
-    __typename
-    ... on PublishSuccessResponse {{
-      post {{
-        id
-        published
-      }}
-    }}
-    ... on PublishErrorResponse {{
-      error {{
-        field
-        message
-        received
-      }}
-    }}
-  }}
-}}"),
+        "query": query,
+    });
+
+    // act
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/")
+        .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(first_page_json_request_body.to_string()))
+        .unwrap();
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(request)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let posts = &body["data"]["posts"];
+    let titles: Vec<&str> = posts["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| edge["node"]["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["Post 3 Title", "Post 2 Title"]);
+    assert_eq!(posts["pageInfo"]["hasNextPage"], json!(true));
+    let end_cursor = posts["pageInfo"]["endCursor"].as_str().unwrap();
+    assert_eq!(posts["edges"][1]["cursor"], json!(end_cursor));
+
+    // arrange
+    let second_page_json_request_body: Value = json!({
+        "operationName":"PostsQuery",
+        "variables":{ "after": end_cursor },
+        "query": query,
+    });
+
+    // act
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/")
+        .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(second_page_json_request_body.to_string()))
+        .unwrap();
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(request)
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let posts = &body["data"]["posts"];
+    let titles: Vec<&str> = posts["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|edge| edge["node"]["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["Post 1 Title"]);
+    assert_eq!(posts["pageInfo"]["hasNextPage"], json!(false));
+}
+
+#[tokio::test]
+async fn post_by_slug_returns_post_or_null() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id = helpers::create_draft(&mut router, "First Post Title", "First post body.").await;
+    let post_json_request_body: Value = json!({
+        "operationName":"PostQuery",
+        "variables":{},
+        "query": format!(r#"query PostQuery {{
+  byId: post(id: "{id}") {{ id slug }}
+  bySlug: postBySlug(slug: "first-post-title") {{ id title }}
+  missing: postBySlug(slug: "missing-post-title") {{ id }}
+}}"#),
     });
 
     // act
@@ -395,7 +538,8 @@ async fn publish_returns_user_error_for_invalid_id() {
                 .method(Method::POST)
                 .uri("/")
                 .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                .body(Body::from(publish_draft_json_request_body.to_string()))
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(post_json_request_body.to_string()))
                 .unwrap(),
         )
         .await
@@ -409,13 +553,62 @@ async fn publish_returns_user_error_for_invalid_id() {
     assert_eq!(
         body,
         json!({
-            "data": { "publish": {
-                "__typename": "PublishErrorResponse",
-                "error": {
-                    "field": "id",
-                    "message": "Did not find draft post with id `9999`",
-                    "received": "9999"
-                }
+            "data": {
+                "byId": { "id": id, "slug": "first-post-title" },
+                "bySlug": { "id": id, "title": "First Post Title" },
+                "missing": null
+            },
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn search_posts_returns_ranked_published_posts() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id_1 = helpers::create_draft(&mut router, "GraphQL Post Title", "About GraphQL.").await;
+    let id_2 = helpers::create_draft(&mut router, "Second Post Title", "Mentions GraphQL.").await;
+    let _id_3 = helpers::create_draft(&mut router, "GraphQL Draft Title", "Draft body.").await;
+    helpers::publish_draft(&mut router, id_1).await;
+    helpers::publish_draft(&mut router, id_2).await;
+    let search_json_request_body: Value = json!({
+        "operationName":"SearchQuery",
+        "variables":{},
+        "query": r#"query SearchQuery {
+  searchPosts(query: "graphql", first: 1) {
+    nodes { titleHighlight post { id } }
+    pageInfo { hasNextPage }
+  }
+}"#,
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(search_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": { "searchPosts": {
+                "nodes": [
+                    { "titleHighlight": "<mark>GraphQL</mark> Post Title", "post": { "id": id_1 } }
+                ],
+                "pageInfo": { "hasNextPage": true }
             }},
             "extensions": { "traceId": "00000000000000000000000000000000" }
         })
@@ -423,33 +616,43 @@ async fn publish_returns_user_error_for_invalid_id() {
 }
 
 #[tokio::test]
-async fn publish_returns_user_expected_result_for_valid_input() {
+async fn posts_resolve_authors_and_users_resolve_posts() {
     // arrange
     let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
-    let _id_1 = helpers::create_draft(&mut router, "First Post Title", "First post body.").await;
-    let id_2 = helpers::create_draft(&mut router, "Second Post Title", "Second post body.").await;
-    let _id_3 = helpers::create_draft(&mut router, "Third Post Title", "Third post body.").await;
-    let publish_draft_json_request_body: Value = json!({
-        "operationName":"PublishMutation",
+    let author_id = helpers::create_user(&mut router, "Ada").await;
+    let create_authored_drafts_json_request_body: Value = json!({
+        "operationName":"CreateDraftMutation",
         "variables":{},
-        "query": format!("mutation PublishMutation {{
-  publish(id: {id_2}) {{
-    __typename
-    ... on PublishSuccessResponse {{
-      post {{
-        id
-        published
-      }}
-    }}
-    ... on PublishErrorResponse {{
-      error {{
-        field
-        message
-        received
-      }}
-    }}
+        "query": r#"mutation CreateDraftMutation {
+  published: createDraft(title: "Authored Post Title", body: "Authored post body.") { id }
+  draft: createDraft(title: "Authored Draft Title", body: "Authored draft body.") { id }
+}"#,
+    });
+    let created = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header_for(
+            &author_id.to_string(),
+            &[],
+        )),
+        &create_authored_drafts_json_request_body,
+    )
+    .await;
+    let published_id = created["data"]["published"]["id"].as_i64().unwrap();
+    let anonymous_id =
+        helpers::create_draft(&mut router, "Anonymous Post Title", "Anonymous post body.").await;
+    helpers::publish_draft(&mut router, published_id).await;
+    helpers::publish_draft(&mut router, anonymous_id).await;
+    let user_json_request_body: Value = json!({
+        "operationName":"UserQuery",
+        "variables":{},
+        "query": format!(r#"query UserQuery {{
+  posts {{ nodes {{ title author {{ name }} }} }}
+  user(id: "{author_id}") {{
+    name
+    posts {{ nodes {{ title }} }}
+    drafts {{ nodes {{ title }} }}
   }}
-}}"),
+}}"#),
     });
 
     // act
@@ -459,7 +662,8 @@ async fn publish_returns_user_expected_result_for_valid_input() {
                 .method(Method::POST)
                 .uri("/")
                 .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
-                .body(Body::from(publish_draft_json_request_body.to_string()))
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(user_json_request_body.to_string()))
                 .unwrap(),
         )
         .await
@@ -473,11 +677,930 @@ async fn publish_returns_user_expected_result_for_valid_input() {
     assert_eq!(
         body,
         json!({
-            "data": { "publish": {
-                "__typename": "PublishSuccessResponse",
-                "post": {
-                    "id": id_2,
-                    "published": true,
+            "data": {
+                "posts": { "nodes": [
+                    { "title": "Anonymous Post Title", "author": null },
+                    { "title": "Authored Post Title", "author": { "name": "Ada" } }
+                ]},
+                "user": {
+                    "name": "Ada",
+                    "posts": { "nodes": [{ "title": "Authored Post Title" }] },
+                    "drafts": { "nodes": [{ "title": "Authored Draft Title" }] }
+                }
+            },
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn posts_resolve_tags_and_filter_by_tag() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let tagged_id = helpers::create_draft(&mut router, "Tagged Post Title", "Tagged body.").await;
+    let untagged_id =
+        helpers::create_draft(&mut router, "Untagged Post Title", "Untagged body.").await;
+    helpers::publish_draft(&mut router, tagged_id).await;
+    helpers::publish_draft(&mut router, untagged_id).await;
+    let add_tags_json_request_body: Value = json!({
+        "operationName":"AddTagsMutation",
+        "variables":{ "postId": tagged_id },
+        "query": r#"mutation AddTagsMutation($postId: Int!) {
+  rust: addTag(postId: $postId, name: "Rust Lang") { __typename }
+  graphql: addTag(postId: $postId, name: "GraphQL") { __typename }
+}"#,
+    });
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(add_tags_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let posts_json_request_body: Value = json!({
+        "operationName":"PostsQuery",
+        "variables":{},
+        "query": r#"query PostsQuery {
+  all: posts { nodes { title tags { name } } }
+  tagged: posts(tag: "rust lang") { nodes { title } }
+}"#,
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(posts_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body["data"],
+        json!({
+            "all": { "nodes": [
+                { "title": "Untagged Post Title", "tags": [] },
+                {
+                    "title": "Tagged Post Title",
+                    "tags": [{ "name": "graphql" }, { "name": "rust-lang" }]
+                }
+            ]},
+            "tagged": { "nodes": [{ "title": "Tagged Post Title" }] }
+        })
+    );
+}
+
+#[tokio::test]
+async fn comments_are_moderated_before_anonymous_viewers_see_them() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let post_id = helpers::create_draft(&mut router, "Commented Post", "Post body.").await;
+    helpers::publish_draft(&mut router, post_id).await;
+    let add_comments_json_request_body: Value = json!({
+        "operationName":"AddCommentsMutation",
+        "variables":{ "postId": post_id },
+        "query": r#"mutation AddCommentsMutation($postId: Int!) {
+  approved: addComment(postId: $postId, authorName: "Ada", body: "Approved comment") {
+    ... on AddCommentSuccessResponse { comment { id } }
+  }
+  pending: addComment(postId: $postId, authorName: "Grace", body: "Pending comment") {
+    ... on AddCommentSuccessResponse { comment { id } }
+  }
+}"#,
+    });
+    let added = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header()),
+        &add_comments_json_request_body,
+    )
+    .await;
+    let approved_id = added["data"]["approved"]["comment"]["id"].as_i64().unwrap();
+    let approve_json_request_body: Value = json!({
+        "operationName":"ApproveCommentMutation",
+        "variables":{ "id": approved_id },
+        "query": "mutation ApproveCommentMutation($id: Int!) { approveComment(id: $id) { __typename } }",
+    });
+    let comments_json_request_body: Value = json!({
+        "operationName":"CommentsQuery",
+        "variables":{ "id": post_id.to_string() },
+        "query": "query CommentsQuery($id: ID!) {
+  post(id: $id) { comments { nodes { authorName body status } } }
+}",
+    });
+    let pending_json_request_body: Value = json!({
+        "operationName":"PendingCommentsQuery",
+        "variables":{},
+        "query": "query PendingCommentsQuery { pendingComments { nodes { body } } }",
+    });
+
+    // act
+    let mut bodies = Vec::new();
+    for (authorization, request_body) in [
+        (
+            Some(TestApp::authorization_header()),
+            &approve_json_request_body,
+        ),
+        (None, &comments_json_request_body),
+        (
+            Some(TestApp::authorization_header()),
+            &comments_json_request_body,
+        ),
+        (
+            Some(TestApp::authorization_header()),
+            &pending_json_request_body,
+        ),
+        (
+            Some(TestApp::authorization_header_for("2", &[])),
+            &pending_json_request_body,
+        ),
+    ] {
+        bodies.push(helpers::send(&mut router, authorization, request_body).await);
+    }
+
+    // assert
+    assert_eq!(
+        bodies[0]["data"],
+        json!({ "approveComment": { "__typename": "ModerateCommentSuccessResponse" } })
+    );
+    assert_eq!(
+        bodies[1]["data"]["post"]["comments"]["nodes"],
+        json!([{ "authorName": "Ada", "body": "Approved comment", "status": "APPROVED" }])
+    );
+    assert_eq!(
+        bodies[2]["data"]["post"]["comments"]["nodes"],
+        json!([
+            { "authorName": "Ada", "body": "Approved comment", "status": "APPROVED" },
+            { "authorName": "Grace", "body": "Pending comment", "status": "PENDING" }
+        ])
+    );
+    assert_eq!(
+        bodies[3]["data"]["pendingComments"]["nodes"],
+        json!([{ "body": "Pending comment" }])
+    );
+    assert_eq!(bodies[4]["errors"][0]["extensions"]["code"], "FORBIDDEN");
+}
+
+#[tokio::test]
+async fn editors_review_revision_diffs_and_restore_revisions() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let post_id = helpers::create_draft(&mut router, "First Title", "First body.").await;
+    let update_json_request_body: Value = json!({
+        "operationName":"UpdateDraftMutation",
+        "variables":{ "id": post_id },
+        "query": r#"mutation UpdateDraftMutation($id: Int!) {
+  updateDraft(id: $id, title: "Second Title", body: "Second body.") { __typename }
+}"#,
+    });
+    helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header()),
+        &update_json_request_body,
+    )
+    .await;
+    helpers::publish_draft(&mut router, post_id).await;
+    let revisions_json_request_body: Value = json!({
+        "operationName":"RevisionsQuery",
+        "variables":{ "id": post_id.to_string() },
+        "query": "query RevisionsQuery($id: ID!) { post(id: $id) { revisions { id title } } }",
+    });
+    let revisions = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header()),
+        &revisions_json_request_body,
+    )
+    .await;
+    let revisions = &revisions["data"]["post"]["revisions"];
+    let (second_id, first_id) = (
+        revisions[0]["id"].as_i64().unwrap(),
+        revisions[1]["id"].as_i64().unwrap(),
+    );
+    let diff_json_request_body: Value = json!({
+        "operationName":"DiffQuery",
+        "variables":{ "id": post_id.to_string(), "from": first_id, "to": second_id },
+        "query": "query DiffQuery($id: ID!, $from: Int!, $to: Int!) {
+  post(id: $id) { diff(from: $from, to: $to) }
+}",
+    });
+    let restore_json_request_body: Value = json!({
+        "operationName":"RestoreRevisionMutation",
+        "variables":{ "postId": post_id, "revisionId": first_id },
+        "query": "mutation RestoreRevisionMutation($postId: Int!, $revisionId: Int!) {
+  restoreRevision(postId: $postId, revisionId: $revisionId) {
+    ... on RestoreRevisionSuccessResponse { post { title body } }
+  }
+}",
+    });
+
+    // act
+    let diff = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header()),
+        &diff_json_request_body,
+    )
+    .await;
+    let forbidden_diff = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header_for("2", &[])),
+        &diff_json_request_body,
+    )
+    .await;
+    let restored = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header()),
+        &restore_json_request_body,
+    )
+    .await;
+
+    // assert
+    assert_eq!(
+        diff["data"]["post"]["diff"],
+        format!(
+            "--- revision {first_id}\n+++ revision {second_id}\n@@ -1,3 +1,3 @@\n-First Title\n\
+             +Second Title\n \n-First body.\n+Second body.\n"
+        )
+    );
+    assert_eq!(
+        forbidden_diff["errors"][0]["extensions"]["code"],
+        "FORBIDDEN"
+    );
+    assert_eq!(
+        restored["data"]["restoreRevision"]["post"],
+        json!({ "title": "First Title", "body": "First body." })
+    );
+}
+
+#[tokio::test]
+async fn mutations_reject_anonymous_callers() {
+    // arrange
+    let ApplicationRouter { router } = TestApp::spawn_routers().await;
+    let create_draft_json_request_body: Value = json!({
+        "operationName":"CreateDraftMutation",
+        "variables":{},
+        "query": r#"mutation CreateDraftMutation {
+  createDraft(title: "Draft title", body: "Draft body text") { id }
+}"#,
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(create_draft_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "Authentication is required",
+                "locations": [{ "line": 2, "column": 3 }],
+                "path": ["createDraft"],
+                "extensions": { "code": "UNAUTHENTICATED" }
+            }],
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn create_user_is_forbidden_for_viewers_without_admin_role() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let create_user_json_request_body: Value = json!({
+        "operationName":"CreateUserMutation",
+        "variables":{},
+        "query": r#"mutation CreateUserMutation { createUser(name: "Ada") { id } }"#,
+    });
+
+    // act
+    let body = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header_for("2", &["editor"])),
+        &create_user_json_request_body,
+    )
+    .await;
+
+    // assert
+    assert_eq!(body["data"], Value::Null);
+    assert_eq!(body["errors"][0]["path"], json!(["createUser"]));
+    assert_eq!(body["errors"][0]["extensions"]["code"], "FORBIDDEN");
+}
+
+#[tokio::test]
+async fn unpublish_is_forbidden_for_viewers_without_editor_role() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id = helpers::create_draft(&mut router, "Draft title", "Draft body text").await;
+    helpers::publish_draft(&mut router, id).await;
+    let unpublish_json_request_body: Value = json!({
+        "operationName":"UnpublishMutation",
+        "variables":{ "id": id },
+        "query": "mutation UnpublishMutation($id: Int!) { unpublish(id: $id) { __typename } }",
+    });
+
+    // act
+    let body = helpers::send(
+        &mut router,
+        Some(TestApp::authorization_header_for("2", &["writer"])),
+        &unpublish_json_request_body,
+    )
+    .await;
+
+    // assert
+    assert_eq!(body["data"], Value::Null);
+    assert_eq!(body["errors"][0]["path"], json!(["unpublish"]));
+    assert_eq!(body["errors"][0]["extensions"]["code"], "FORBIDDEN");
+}
+
+#[tokio::test]
+async fn drafts_are_only_written_by_and_updated_by_their_author_or_admins() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let author_id = helpers::create_user(&mut router, "Ada").await;
+    let author_authorization =
+        TestApp::authorization_header_for(&author_id.to_string(), &["editor"]);
+    let create_draft_json_request_body: Value = json!({
+        "operationName":"CreateDraftMutation",
+        "variables":{},
+        "query": r#"mutation CreateDraftMutation {
+  createDraft(title: "Authored Draft Title", body: "Authored draft body.") { id author { name } }
+}"#,
+    });
+    let created = helpers::send(
+        &mut router,
+        Some(author_authorization.clone()),
+        &create_draft_json_request_body,
+    )
+    .await;
+    let id = created["data"]["createDraft"]["id"].as_i64().unwrap();
+    let update_draft_json_request_body: Value = json!({
+        "operationName":"UpdateDraftMutation",
+        "variables":{ "id": id },
+        "query": r#"mutation UpdateDraftMutation($id: Int!) {
+  updateDraft(id: $id, title: "Updated Title", body: "Updated body.") { __typename }
+}"#,
+    });
+
+    // act
+    let mut outcomes = Vec::new();
+    for authorization in [
+        TestApp::authorization_header_for("999", &["editor"]),
+        author_authorization,
+        TestApp::authorization_header(),
+    ] {
+        let body = helpers::send(
+            &mut router,
+            Some(authorization),
+            &update_draft_json_request_body,
+        )
+        .await;
+        outcomes.push((
+            body["data"]["updateDraft"]["__typename"].clone(),
+            body["errors"][0]["extensions"]["code"].clone(),
+        ));
+    }
+
+    // assert
+    assert_eq!(
+        created["data"]["createDraft"]["author"],
+        json!({ "name": "Ada" })
+    );
+    assert_eq!(
+        outcomes,
+        [
+            (Value::Null, json!("FORBIDDEN")),
+            (json!("UpdateDraftSuccessResponse"), Value::Null),
+            (json!("UpdateDraftSuccessResponse"), Value::Null)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tags_are_only_changed_by_editors_and_post_author() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let author_id = helpers::create_user(&mut router, "Ada").await;
+    let author_authorization =
+        TestApp::authorization_header_for(&author_id.to_string(), &["writer"]);
+    let create_draft_json_request_body: Value = json!({
+        "operationName":"CreateDraftMutation",
+        "variables":{},
+        "query": r#"mutation CreateDraftMutation {
+  createDraft(title: "Tagged Draft Title", body: "Tagged draft body.") { id }
+}"#,
+    });
+    let created = helpers::send(
+        &mut router,
+        Some(author_authorization.clone()),
+        &create_draft_json_request_body,
+    )
+    .await;
+    let id = created["data"]["createDraft"]["id"].as_i64().unwrap();
+    let add_tag_json_request_body: Value = json!({
+        "operationName":"AddTagMutation",
+        "variables":{ "postId": id },
+        "query": r#"mutation AddTagMutation($postId: Int!) {
+  addTag(postId: $postId, name: "rust") { __typename }
+}"#,
+    });
+    let remove_tag_json_request_body: Value = json!({
+        "operationName":"RemoveTagMutation",
+        "variables":{ "postId": id },
+        "query": r#"mutation RemoveTagMutation($postId: Int!) {
+  removeTag(postId: $postId, name: "rust") { __typename }
+}"#,
+    });
+
+    // act
+    let mut outcomes = Vec::new();
+    for (authorization, request_body, field) in [
+        (
+            TestApp::authorization_header_for("999", &["writer"]),
+            &add_tag_json_request_body,
+            "addTag",
+        ),
+        (author_authorization, &add_tag_json_request_body, "addTag"),
+        (
+            TestApp::authorization_header_for("999", &["writer"]),
+            &remove_tag_json_request_body,
+            "removeTag",
+        ),
+        (
+            TestApp::authorization_header_for("999", &["editor"]),
+            &remove_tag_json_request_body,
+            "removeTag",
+        ),
+    ] {
+        let body = helpers::send(&mut router, Some(authorization), request_body).await;
+        outcomes.push((
+            body["data"][field]["__typename"].clone(),
+            body["errors"][0]["extensions"]["code"].clone(),
+        ));
+    }
+
+    // assert
+    assert_eq!(
+        outcomes,
+        [
+            (Value::Null, json!("FORBIDDEN")),
+            (json!("AddTagSuccessResponse"), Value::Null),
+            (Value::Null, json!("FORBIDDEN")),
+            (json!("RemoveTagSuccessResponse"), Value::Null)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn publish_is_forbidden_for_viewers_without_editor_role() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id = helpers::create_draft(&mut router, "Draft title", "Draft body text").await;
+    let publish_json_request_body: Value = json!({
+        "operationName":"PublishMutation",
+        "variables":{},
+        "query": format!("mutation PublishMutation {{
+  publish(id: {id}) {{ __typename }}
+}}"),
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(
+                    header::AUTHORIZATION,
+                    TestApp::authorization_header_for("2", &["writer"]),
+                )
+                .body(Body::from(publish_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "Viewer is not permitted to perform this operation",
+                "locations": [{ "line": 2, "column": 3 }],
+                "path": ["publish"],
+                "extensions": { "code": "FORBIDDEN" }
+            }],
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn drafts_are_only_visible_to_admins_and_their_author() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let author_id = helpers::create_user(&mut router, "Ada").await;
+    let drafts_json_request_body: Value = json!({
+        "operationName":"DraftsQuery",
+        "variables":{},
+        "query": format!(r#"query DraftsQuery {{
+  user(id: "{author_id}") {{ drafts {{ nodes {{ id }} }} }}
+}}"#),
+    });
+    let request = |authorization: Option<String>| {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref());
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+
+        builder
+            .body(Body::from(drafts_json_request_body.to_string()))
+            .unwrap()
+    };
+
+    // act
+    let mut codes = Vec::new();
+    for authorization in [
+        None,
+        Some(TestApp::authorization_header_for("999", &["editor"])),
+        Some(TestApp::authorization_header_for(
+            &author_id.to_string(),
+            &[],
+        )),
+    ] {
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(request(authorization))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        codes.push(body["errors"][0]["extensions"]["code"].clone());
+    }
+
+    // assert
+    assert_eq!(
+        codes,
+        vec![json!("UNAUTHENTICATED"), json!("FORBIDDEN"), Value::Null]
+    );
+}
+
+#[tokio::test]
+async fn queries_exceeding_limits_report_cost_with_separate_introspection_limits() {
+    // arrange
+    let config = ApplicationConfig {
+        query_limits: QueryLimits {
+            max_depth: 2,
+            max_complexity: 100,
+            max_aliases: 1,
+            max_introspection_depth: 5,
+            max_introspection_complexity: 100,
+            max_introspection_aliases: 2,
+        },
+        ..TestApp::config()
+    };
+    let ApplicationRouter { mut router } = ApplicationRouter::build("sqlite://:memory:", config)
+        .await
+        .unwrap();
+    let queries = [
+        "query { first: hello second: hello }",
+        "query { posts { nodes { id } } }",
+        "query { __schema { types { fields { type { name } } } } }",
+        "query { __schema { types { fields { type { ofType { name } } } } } }",
+        "query { a: __typename b: __typename c: __typename }",
+    ];
+
+    // act
+    let mut errors = Vec::new();
+    for query in queries {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(json!({ "query": query }).to_string()))
+            .unwrap();
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        errors.push(body["errors"].clone());
+    }
+
+    // assert
+    assert_eq!(
+        errors,
+        vec![
+            json!([{
+                "message": "Query has too many aliases: 2 exceeds limit of 1",
+                "extensions": { "code": "TOO_MANY_ALIASES", "cost": 2, "limit": 1 }
+            }]),
+            json!([{
+                "message": "Query is nested too deep: 3 exceeds limit of 2",
+                "extensions": { "code": "QUERY_TOO_DEEP", "cost": 3, "limit": 2 }
+            }]),
+            Value::Null,
+            json!([{
+                "message": "Query is nested too deep: 6 exceeds limit of 5",
+                "extensions": { "code": "QUERY_TOO_DEEP", "cost": 6, "limit": 5 }
+            }]),
+            json!([{
+                "message": "Query has too many aliases: 3 exceeds limit of 2",
+                "extensions": { "code": "TOO_MANY_ALIASES", "cost": 3, "limit": 2 }
+            }]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn publish_returns_user_error_for_invalid_id() {
+    // arrange
+    let ApplicationRouter { router } = TestApp::spawn_routers().await;
+    let id = 9_999;
+    let publish_draft_json_request_body: Value = json!({
+        "operationName":"PublishMutation",
+        "variables":{},
+        "query": format!("mutation PublishMutation {{
+  publish(id: {id}) {{
+    __typename
+    ... on PublishSuccessResponse {{
+      post {{
+        id
+        published
+      }}
+    }}
+    ... on PublishErrorResponse {{
+      error {{
+        field
+        message
+        received
+      }}
+    }}
+  }}
+}}"),
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(publish_draft_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": { "publish": {
+                "__typename": "PublishErrorResponse",
+                "error": {
+                    "field": "id",
+                    "message": "Did not find draft post with id `9999`",
+                    "received": "9999"
+                }
+            }},
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn publish_returns_user_expected_result_for_valid_input() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let _id_1 = helpers::create_draft(&mut router, "First Post Title", "First post body.").await;
+    let id_2 = helpers::create_draft(&mut router, "Second Post Title", "Second post body.").await;
+    let _id_3 = helpers::create_draft(&mut router, "Third Post Title", "Third post body.").await;
+    let publish_draft_json_request_body: Value = json!({
+        "operationName":"PublishMutation",
+        "variables":{},
+        "query": format!("mutation PublishMutation {{
+  publish(id: {id_2}) {{
+    __typename
+    ... on PublishSuccessResponse {{
+      post {{
+        id
+        published
+      }}
+    }}
+    ... on PublishErrorResponse {{
+      error {{
+        field
+        message
+        received
+      }}
+    }}
+  }}
+}}"),
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(publish_draft_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": { "publish": {
+                "__typename": "PublishSuccessResponse",
+                "post": {
+                    "id": id_2,
+                    "published": true,
+                }
+            }},
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn update_draft_returns_user_expected_result_for_valid_input() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id = helpers::create_draft(&mut router, "First Post Title", "First post body.").await;
+    let update_draft_json_request_body: Value = json!({
+        "operationName":"UpdateDraftMutation",
+        "variables":{},
+        "query": format!(r#"mutation UpdateDraftMutation {{
+  updateDraft(id: {id}, title: "Updated Post Title", body: "Updated post body.") {{
+    __typename
+    ... on UpdateDraftSuccessResponse {{
+      post {{
+        id
+        title
+        body
+      }}
+    }}
+    ... on UpdateDraftErrorResponse {{
+      error {{
+        field
+        message
+        received
+      }}
+    }}
+  }}
+}}"#),
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(update_draft_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": { "updateDraft": {
+                "__typename": "UpdateDraftSuccessResponse",
+                "post": {
+                    "id": id,
+                    "title": "Updated Post Title",
+                    "body": "Updated post body."
+                }
+            }},
+            "extensions": { "traceId": "00000000000000000000000000000000" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn unpublish_returns_user_expected_result_for_valid_input() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let id = helpers::create_draft(&mut router, "First Post Title", "First post body.").await;
+    helpers::publish_draft(&mut router, id).await;
+    let unpublish_json_request_body: Value = json!({
+        "operationName":"UnpublishMutation",
+        "variables":{},
+        "query": format!("mutation UnpublishMutation {{
+  unpublish(id: {id}) {{
+    __typename
+    ... on UnpublishSuccessResponse {{
+      post {{
+        id
+        published
+      }}
+    }}
+    ... on UnpublishErrorResponse {{
+      error {{
+        field
+        message
+        received
+      }}
+    }}
+  }}
+}}"),
+    });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(unpublish_json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": { "unpublish": {
+                "__typename": "UnpublishSuccessResponse",
+                "post": {
+                    "id": id,
+                    "published": false,
                 }
             }},
             "extensions": { "traceId": "00000000000000000000000000000000" }
@@ -520,6 +1643,7 @@ async fn delete_draft_returns_user_error_for_invalid_id() {
                 .method(Method::POST)
                 .uri("/")
                 .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
                 .body(Body::from(delete_draft_json_request_body.to_string()))
                 .unwrap(),
         )
@@ -584,6 +1708,7 @@ async fn delete_draft_returns_user_expected_result_for_valid_input() {
                 .method(Method::POST)
                 .uri("/")
                 .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
                 .body(Body::from(delete_draft_json_request_body.to_string()))
                 .unwrap(),
         )
@@ -0,0 +1,79 @@
+use crate::helpers::TestApp;
+use axum_graphql::model::{
+    post::{Post, PostFilter, PostPageRequest, create_draft_mutation, drafts_query},
+    user::{User, create_user_mutation, user_query, users_query},
+};
+
+#[tokio::test]
+async fn create_user_mutation_creates_user() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+
+    // act
+    let User { id, name, .. } = create_user_mutation(&db_pool, "Ada").await.unwrap();
+
+    // assert
+    assert_eq!(name, "Ada");
+    let user = user_query(&db_pool, id).await.unwrap().unwrap();
+    assert_eq!(user.name, "Ada");
+}
+
+#[tokio::test]
+async fn user_query_returns_none_for_missing_user() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+
+    // act
+    let outcome = user_query(&db_pool, 9_999).await.unwrap();
+
+    // assert
+    assert_eq!(outcome, None);
+}
+
+#[tokio::test]
+async fn users_query_returns_only_existing_users() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let first = create_user_mutation(&db_pool, "Ada").await.unwrap();
+    let second = create_user_mutation(&db_pool, "Grace").await.unwrap();
+
+    // act
+    let mut outcome = users_query(&db_pool, &[second.id, 9_999, first.id])
+        .await
+        .unwrap();
+
+    // assert
+    outcome.sort_by_key(|user| user.id);
+    assert_eq!(outcome, vec![first, second]);
+}
+
+#[tokio::test]
+async fn drafts_query_filters_by_author() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let User { id: author_id, .. } = create_user_mutation(&db_pool, "Ada").await.unwrap();
+    let Post { id, .. } =
+        create_draft_mutation(&db_pool, "Ada Draft", "Draft body.", Some(author_id))
+            .await
+            .unwrap();
+    let _ = create_draft_mutation(&db_pool, "Anonymous Draft", "Draft body.", None)
+        .await
+        .unwrap();
+
+    // act
+    let outcome = drafts_query(
+        &db_pool,
+        &PostFilter {
+            author_id: Some(author_id),
+            ..PostFilter::default()
+        },
+        &PostPageRequest::default(),
+    )
+    .await
+    .unwrap();
+
+    // assert
+    assert_eq!(outcome.posts.len(), 1);
+    assert_eq!(outcome.posts[0].id, id);
+    assert_eq!(outcome.posts[0].author_id, Some(author_id));
+}
@@ -1,13 +1,30 @@
+use assert_fs::prelude::*;
 use axum::{
     body::Body,
-    http::{Method, Request, StatusCode, header},
+    http::{HeaderValue, Method, Request, StatusCode, header},
 };
+use futures::{SinkExt, StreamExt};
 use http_body_util::BodyExt;
+use opentelemetry::trace::{SpanId, TraceId, TracerProvider};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use reqwest::Client;
 use serde_json::{Value, json};
-use tower::util::ServiceExt;
+use sha2::{Digest, Sha256};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{Message, client::IntoClientRequest},
+};
+use tower::{Service, util::ServiceExt};
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::layer::SubscriberExt;
 
 use crate::helpers::TestApp;
-use axum_graphql::startup::ApplicationRouter;
+use axum_graphql::{
+    model::cover_image::CoverImageStorage,
+    observability::{metrics::MetricsEndpoint, tracing::init_propagator},
+    routes::persisted_queries::PersistedQueries,
+    startup::{ApplicationConfig, ApplicationRouter},
+};
 
 #[tokio::test]
 async fn graphql_endpoint_returns_200_ok() {
@@ -16,12 +33,121 @@ async fn graphql_endpoint_returns_200_ok() {
 
     // act
     let response = router
-        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header(header::ACCEPT, "text/html,application/xhtml+xml,*/*;q=0.8")
+                .body(Body::empty())
+                .unwrap(),
+        )
         .await
         .unwrap();
 
     // assert
     assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+}
+
+#[tokio::test]
+async fn graphql_endpoint_serves_queries_over_get() {
+    // arrange
+    let ApplicationRouter { router } = TestApp::spawn_routers().await;
+    let uri = get_uri(&[
+        (
+            "query",
+            "query HelloQuery($id: ID!) { hello post(id: $id) { id } }",
+        ),
+        ("variables", r#"{"id": "1"}"#),
+        ("operationName", "HelloQuery"),
+    ]);
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(uri)
+                .header(header::ACCEPT, "application/graphql-response+json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/graphql-response+json"
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body["data"],
+        json!({ "hello": "Hello everybody!", "post": null })
+    );
+}
+
+#[tokio::test]
+async fn graphql_endpoint_rejects_get_requests_without_a_query() {
+    // arrange
+    let ApplicationRouter { router } = TestApp::spawn_routers().await;
+
+    // act
+    let response = router
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+}
+
+#[tokio::test]
+async fn graphql_endpoint_uses_4xx_status_codes_for_graphql_response_media_type() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let invalid_query = json!({ "query": "query { hello " }).to_string();
+    let unknown_field = json!({ "query": "query { goodbye }" }).to_string();
+
+    // act
+    let mut responses = Vec::new();
+    for (accept, body) in [
+        ("application/graphql-response+json", invalid_query.clone()),
+        ("application/graphql-response+json", unknown_field),
+        ("application/json", invalid_query),
+        ("text/plain", String::from("{}")),
+    ] {
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/")
+                    .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .header(header::ACCEPT, accept)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        responses.push(response.status());
+    }
+
+    // assert
+    assert_eq!(
+        responses,
+        vec![
+            StatusCode::BAD_REQUEST,
+            StatusCode::BAD_REQUEST,
+            StatusCode::OK,
+            StatusCode::NOT_ACCEPTABLE,
+        ]
+    );
 }
 
 #[tokio::test]
@@ -63,6 +189,533 @@ async fn graphql_endpoint_responds_to_invalid_query() {
     );
 }
 
+#[tokio::test]
+async fn graphql_endpoint_rejects_invalid_bearer_token() {
+    // arrange
+    let ApplicationRouter { router } = TestApp::spawn_routers().await;
+    let json_request_body: Value = json!(
+    {"operationName":"HelloQuery","variables":{},"query":"query HelloQuery { hello }"
+        });
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, "Bearer not-a-valid-token")
+                .body(Body::from(json_request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        r#"Bearer error="invalid_token""#
+    );
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "data": null,
+            "errors": [{
+                "message": "Bearer token is invalid or has expired",
+                "extensions": { "code": "UNAUTHENTICATED" }
+            }]
+        })
+    );
+}
+
+#[tokio::test]
+async fn graphql_execution_span_is_child_of_remote_span_from_traceparent_header() {
+    // arrange
+    let exporter = InMemorySpanExporter::default();
+    let tracer_provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(OpenTelemetryLayer::new(tracer_provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+    init_propagator();
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+
+    // act
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(
+                    "traceparent",
+                    "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+                )
+                .body(Body::from(
+                    json!({ "query": "query HelloQuery { hello }" }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body["extensions"]["traceId"],
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+    let spans = exporter.get_finished_spans().unwrap();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "graphql_execution")
+        .unwrap();
+    assert_eq!(
+        span.span_context.trace_id(),
+        TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+    );
+    assert_eq!(
+        span.parent_span_id,
+        SpanId::from_hex("00f067aa0ba902b7").unwrap()
+    );
+}
+
+#[tokio::test]
+async fn graphql_endpoint_sets_cache_control_and_vary_from_hints() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let requests = [
+        ("query { hello }", None),
+        ("query { hello posts { nodes { id } } }", None),
+        (
+            "query { posts { nodes { id } } }",
+            Some(TestApp::authorization_header()),
+        ),
+        (
+            r#"mutation { createUser(name: "Ada") { id } }"#,
+            Some(TestApp::authorization_header()),
+        ),
+    ];
+
+    // act
+    let mut cache_controls = Vec::new();
+    for (query, authorization) in requests {
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref());
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(
+                builder
+                    .body(Body::from(json!({ "query": query }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let headers = response.headers();
+        cache_controls.push((
+            headers
+                .get(header::CACHE_CONTROL)
+                .map(|value| value.to_str().unwrap().to_owned()),
+            headers
+                .get_all(header::VARY)
+                .iter()
+                .any(|value| value.to_str().unwrap().contains("Authorization")),
+        ));
+    }
+
+    // assert
+    assert_eq!(
+        cache_controls,
+        vec![
+            (Some(String::from("public, max-age=3600")), true),
+            (Some(String::from("public, max-age=60")), true),
+            (Some(String::from("private, max-age=60")), true),
+            (None, false),
+        ]
+    );
+}
+
+/// `/` URI with `params` URL-encoded in the query string
+fn get_uri(params: &[(&str, &str)]) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+
+    format!("/?{query}")
+}
+
+#[tokio::test]
+async fn graphql_endpoint_serves_automatic_persisted_queries_over_post_and_get() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let query = "query HelloQuery { hello }";
+    let hash = format!("{:x}", Sha256::digest(query));
+    let extensions = json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } });
+    let get_request = || {
+        Request::builder()
+            .uri(get_uri(&[("extensions", &extensions.to_string())]))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // act
+    let mut bodies = Vec::new();
+    for request in [
+        get_request(),
+        Request::builder()
+            .method(Method::POST)
+            .uri("/")
+            .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+            .body(Body::from(
+                json!({ "query": query, "extensions": extensions }).to_string(),
+            ))
+            .unwrap(),
+        get_request(),
+    ] {
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        bodies.push(body);
+    }
+
+    // assert
+    assert_eq!(
+        bodies,
+        vec![
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "PersistedQueryNotFound",
+                    "extensions": { "code": "PERSISTED_QUERY_NOT_FOUND" }
+                }]
+            }),
+            json!({
+                "data": { "hello": "Hello everybody!" },
+                "extensions": { "traceId": "00000000000000000000000000000000" }
+            }),
+            json!({
+                "data": { "hello": "Hello everybody!" },
+                "extensions": { "traceId": "00000000000000000000000000000000" }
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn graphql_endpoint_rejects_mutations_over_get() {
+    // arrange
+    let ApplicationRouter { mut router } = TestApp::spawn_routers().await;
+    let query = r#"mutation { createUser(name: "Ada") { id } }"#;
+    let hash = format!("{:x}", Sha256::digest(query));
+    let extensions = json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } });
+
+    // act
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .uri(get_uri(&[
+                    ("query", query),
+                    ("extensions", &extensions.to_string()),
+                ]))
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()[header::ALLOW], "POST");
+}
+
+#[tokio::test]
+async fn graphql_endpoint_only_runs_allowlisted_queries_in_allowlist_mode() {
+    // arrange
+    let allowed = "query HelloQuery { hello }";
+    let hash = format!("{:x}", Sha256::digest(allowed));
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let allowlist = temp_dir.child("allowlist.json");
+    allowlist
+        .write_str(&json!({ hash.clone(): allowed }).to_string())
+        .unwrap();
+    let config = ApplicationConfig {
+        persisted_queries: PersistedQueries::allowlist_from_file(allowlist.path()).unwrap(),
+        ..TestApp::config()
+    };
+    let ApplicationRouter { mut router } = ApplicationRouter::build("sqlite://:memory:", config)
+        .await
+        .unwrap();
+    let extensions = json!({ "persistedQuery": { "version": 1, "sha256Hash": hash } });
+
+    // act
+    let mut bodies = Vec::new();
+    for body in [
+        json!({ "extensions": extensions }),
+        json!({ "query": "query { __typename }" }),
+    ] {
+        let response = ServiceExt::<Request<Body>>::ready(&mut router)
+            .await
+            .unwrap()
+            .call(
+                Request::builder()
+                    .method(Method::POST)
+                    .uri("/")
+                    .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        bodies.push(body);
+    }
+
+    // assert
+    assert_eq!(bodies[0]["data"], json!({ "hello": "Hello everybody!" }));
+    assert_eq!(
+        bodies[1]["errors"][0]["extensions"]["code"],
+        "PERSISTED_QUERY_NOT_ALLOWED"
+    );
+}
+
+/// Upload cover image mutation, only selecting the response type name
+const UPLOAD_COVER_IMAGE_TYPENAME: &str = "mutation ($postId: Int!, $file: Upload!) {
+    uploadCoverImage(postId: $postId, file: $file) { __typename }
+}";
+
+/// Multipart request body, separated by `boundary`, running upload cover image mutation `query`
+/// for the post with `post_id`, sending `content` as the file
+fn cover_image_multipart_body(
+    boundary: &str,
+    query: &str,
+    post_id: i64,
+    content: &[u8],
+) -> Vec<u8> {
+    let operations = json!({
+        "query": query,
+        "variables": { "postId": post_id, "file": null }
+    });
+    let mut multipart_body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"operations\"\r\n\r\n{operations}\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"map\"\r\n\r\n{{\"0\": [\"variables.file\"]}}\r\n\
+         --{boundary}\r\nContent-Disposition: form-data; name=\"0\"; filename=\"cover.png\"\r\n\
+         Content-Type: image/png\r\n\r\n"
+    )
+    .into_bytes();
+    multipart_body.extend_from_slice(content);
+    multipart_body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    multipart_body
+}
+
+#[tokio::test]
+async fn graphql_endpoint_accepts_multipart_cover_image_uploads_and_serves_them() {
+    // arrange
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let config = ApplicationConfig {
+        cover_images: CoverImageStorage {
+            directory: temp_dir.path().to_path_buf(),
+            ..CoverImageStorage::default()
+        },
+        ..TestApp::config()
+    };
+    let ApplicationRouter { mut router } = ApplicationRouter::build("sqlite://:memory:", config)
+        .await
+        .unwrap();
+    let png: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x03\0\0\0\x02\x08\x06\0\0\0";
+    let create_draft = json!({
+        "query": r#"mutation { createDraft(title: "Cover Draft", body: "Draft body.") { id } }"#
+    });
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(create_draft.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let post_id = body["data"]["createDraft"]["id"].as_i64().unwrap();
+    let query = "mutation ($postId: Int!, $file: Upload!) {
+        uploadCoverImage(postId: $postId, file: $file) {
+            ... on UploadCoverImageSuccessResponse { post { coverImage { url width height } } }
+        }
+    }";
+    let boundary = "cover-image-boundary";
+    let multipart_body = cover_image_multipart_body(boundary, query, post_id, png);
+
+    // act
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(multipart_body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let cover_image = &body["data"]["uploadCoverImage"]["post"]["coverImage"];
+    assert_eq!(cover_image["width"], 3);
+    assert_eq!(cover_image["height"], 2);
+    let url = cover_image["url"].as_str().unwrap();
+    let response = router
+        .oneshot(Request::builder().uri(url).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body.as_ref(), png);
+}
+
+#[tokio::test]
+async fn graphql_endpoint_rejects_multipart_uploads_over_size_limit_while_reading() {
+    // arrange
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let config = ApplicationConfig {
+        cover_images: CoverImageStorage {
+            directory: temp_dir.path().to_path_buf(),
+            max_bytes: 256,
+        },
+        ..TestApp::config()
+    };
+    let ApplicationRouter { router } = ApplicationRouter::build("sqlite://:memory:", config)
+        .await
+        .unwrap();
+    let boundary = "cover-image-boundary";
+    let multipart_body =
+        cover_image_multipart_body(boundary, UPLOAD_COVER_IMAGE_TYPENAME, 1, &[0; 1_024]);
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(multipart_body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn graphql_endpoint_forbids_cover_image_uploads_by_viewers_other_than_editors_and_author() {
+    // arrange
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let config = ApplicationConfig {
+        cover_images: CoverImageStorage {
+            directory: temp_dir.path().to_path_buf(),
+            ..CoverImageStorage::default()
+        },
+        ..TestApp::config()
+    };
+    let ApplicationRouter { mut router } = ApplicationRouter::build("sqlite://:memory:", config)
+        .await
+        .unwrap();
+    let create_draft = json!({
+        "query": r#"mutation { createDraft(title: "Cover Draft", body: "Draft body.") { id } }"#
+    });
+    let response = ServiceExt::<Request<Body>>::ready(&mut router)
+        .await
+        .unwrap()
+        .call(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(header::AUTHORIZATION, TestApp::authorization_header())
+                .body(Body::from(create_draft.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let post_id = body["data"]["createDraft"]["id"].as_i64().unwrap();
+    let boundary = "cover-image-boundary";
+    let multipart_body =
+        cover_image_multipart_body(boundary, UPLOAD_COVER_IMAGE_TYPENAME, post_id, b"cover");
+
+    // act
+    let response = router
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/")
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .header(
+                    header::AUTHORIZATION,
+                    TestApp::authorization_header_for("2", &["writer"]),
+                )
+                .body(Body::from(multipart_body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // assert
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["data"], Value::Null);
+    assert_eq!(body["errors"][0]["extensions"]["code"], "FORBIDDEN");
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
 #[tokio::test]
 async fn health_check_returns_expected_json_response_with_200_ok() {
     // arrange
@@ -86,3 +739,258 @@ async fn health_check_returns_expected_json_response_with_200_ok() {
     let body: Value = serde_json::from_slice(&body).unwrap();
     assert_eq!(body, json!({ "healthy": true }));
 }
+
+#[tokio::test]
+async fn metrics_endpoint_is_served_on_main_router_only_when_configured() {
+    // arrange
+    let database_url = "sqlite://:memory:";
+    let config = ApplicationConfig {
+        metrics_endpoint: MetricsEndpoint::MainRouter,
+        ..TestApp::config()
+    };
+    let ApplicationRouter { router } = ApplicationRouter::build(database_url, config)
+        .await
+        .unwrap();
+    let ApplicationRouter {
+        router: default_router,
+    } = TestApp::spawn_routers().await;
+
+    // act
+    let response = router
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let default_response = default_router
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/plain; version=0.0.4"
+    );
+    assert_eq!(default_response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Open a `graphql-transport-ws` connection to the app on `port`, sending `payload` with the
+/// `connection_init` message, and return the socket along with the server's reply
+async fn subscription_socket(
+    port: u16,
+    payload: Value,
+) -> (
+    WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
+) {
+    let mut request = format!("ws://localhost:{port}/ws")
+        .into_client_request()
+        .unwrap();
+    request.headers_mut().insert(
+        header::SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static("graphql-transport-ws"),
+    );
+    let (mut socket, _) = connect_async(request).await.unwrap();
+    socket
+        .send(Message::text(
+            json!({ "type": "connection_init", "payload": payload }).to_string(),
+        ))
+        .await
+        .unwrap();
+    let message = socket.next().await.unwrap().unwrap();
+
+    (socket, message)
+}
+
+#[tokio::test]
+async fn subscription_endpoint_streams_created_drafts() {
+    // arrange
+    let TestApp { port, .. } = TestApp::spawn().await;
+    let (mut socket, message) = subscription_socket(
+        port,
+        json!({ "Authorization": TestApp::authorization_header() }),
+    )
+    .await;
+    let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(message, json!({ "type": "connection_ack" }));
+    socket
+        .send(Message::text(
+            json!({
+                "id": "1",
+                "type": "subscribe",
+                "payload": { "query": "subscription { postCreated { id title published } }" }
+            })
+            .to_string(),
+        ))
+        .await
+        .unwrap();
+
+    // give the server a moment to register the subscription before mutating
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    // act
+    let create_draft_json_request_body: Value = json!({
+        "operationName":"CreateDraftMutation",
+        "variables":{},
+        "query": r#"mutation CreateDraftMutation {
+                 createDraft(title: "Draft title", body: "Draft body text") { id }
+            }"#,
+    });
+    let response = Client::new()
+        .post(format!("http://localhost:{port}/"))
+        .header(header::AUTHORIZATION, TestApp::authorization_header())
+        .json(&create_draft_json_request_body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // assert
+    let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(
+        message,
+        json!({
+            "id": "1",
+            "type": "next",
+            "payload": {
+                "data": {
+                    "postCreated": { "id": 1, "title": "Draft title", "published": false }
+                }
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn subscription_endpoint_only_streams_drafts_to_admins() {
+    // arrange
+    let TestApp { port, .. } = TestApp::spawn().await;
+    let (mut anonymous_socket, _) = subscription_socket(port, Value::Null).await;
+    let (mut writer_socket, _) = subscription_socket(
+        port,
+        json!({ "authorization": TestApp::authorization_header_for("2", &[]) }),
+    )
+    .await;
+
+    // act
+    let mut messages = Vec::new();
+    for socket in [&mut anonymous_socket, &mut writer_socket] {
+        for (id, query) in [
+            ("1", "subscription { postCreated { id } }"),
+            ("2", "subscription { draftDeleted { id } }"),
+        ] {
+            socket
+                .send(Message::text(
+                    json!({ "id": id, "type": "subscribe", "payload": { "query": query } })
+                        .to_string(),
+                ))
+                .await
+                .unwrap();
+            // each denied subscription sends an error result, then completes
+            for _ in 0..2 {
+                let message =
+                    tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+                        .await
+                        .unwrap()
+                        .unwrap()
+                        .unwrap();
+                let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+                messages.push(message);
+            }
+        }
+    }
+
+    // assert
+    let outcomes: Vec<_> = messages
+        .iter()
+        .map(|message| {
+            (
+                message["type"].clone(),
+                message["payload"]["errors"][0]["extensions"]["code"].clone(),
+            )
+        })
+        .collect();
+    let denied = |code: &str| {
+        [
+            (json!("next"), json!(code)),
+            (json!("complete"), Value::Null),
+        ]
+    };
+    assert_eq!(
+        outcomes,
+        [
+            denied("UNAUTHENTICATED"),
+            denied("UNAUTHENTICATED"),
+            denied("FORBIDDEN"),
+            denied("FORBIDDEN")
+        ]
+        .concat()
+    );
+}
+
+#[tokio::test]
+async fn subscription_endpoint_closes_connections_with_invalid_bearer_token() {
+    // arrange
+    let TestApp { port, .. } = TestApp::spawn().await;
+
+    // act
+    let (_, message) =
+        subscription_socket(port, json!({ "Authorization": "Bearer not-a-token" })).await;
+
+    // assert
+    let Message::Close(Some(frame)) = message else {
+        panic!("connection should be closed, but received {message:?}");
+    };
+    assert_eq!(frame.reason, "Bearer token is invalid or has expired");
+}
+
+#[tokio::test]
+async fn subscription_endpoint_only_runs_allowlisted_queries_in_allowlist_mode() {
+    // arrange
+    let allowed = "subscription { postPublished { id } }";
+    let temp_dir = assert_fs::TempDir::new().unwrap();
+    let allowlist = temp_dir.child("allowlist.json");
+    allowlist
+        .write_str(&json!({ format!("{:x}", Sha256::digest(allowed)): allowed }).to_string())
+        .unwrap();
+    let TestApp { port, .. } = TestApp::spawn_with_config(ApplicationConfig {
+        persisted_queries: PersistedQueries::allowlist_from_file(allowlist.path()).unwrap(),
+        ..TestApp::config()
+    })
+    .await;
+    let (mut socket, _) = subscription_socket(
+        port,
+        json!({ "Authorization": TestApp::authorization_header() }),
+    )
+    .await;
+
+    // act
+    socket
+        .send(Message::text(
+            json!({
+                "id": "1",
+                "type": "subscribe",
+                "payload": { "query": "subscription { postCreated { id title body } }" }
+            })
+            .to_string(),
+        ))
+        .await
+        .unwrap();
+    let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    // assert
+    let message: Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(
+        message["payload"]["errors"][0]["extensions"]["code"],
+        "PERSISTED_QUERY_NOT_ALLOWED"
+    );
+}
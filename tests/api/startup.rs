@@ -1,12 +1,15 @@
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    http::{Request, StatusCode, header},
 };
 use reqwest::Client;
 use tower::ServiceExt;
 
 use crate::helpers::TestApp;
-use axum_graphql::startup::ApplicationRouter;
+use axum_graphql::{
+    observability::metrics::MetricsEndpoint,
+    startup::{ApplicationConfig, ApplicationRouter},
+};
 
 #[tokio::test]
 async fn application_router_build_successfully_creates_main_and_metrics_routers() {
@@ -14,10 +17,17 @@ async fn application_router_build_successfully_creates_main_and_metrics_routers(
     let database_url = "sqlite://:memory:";
 
     // act
-    let routers = ApplicationRouter::build(database_url).await.unwrap();
+    let routers = ApplicationRouter::build(database_url, TestApp::config())
+        .await
+        .unwrap();
     let ApplicationRouter { router } = routers;
     let main_server_response = router
-        .oneshot(Request::get("/").body(Body::empty()).unwrap())
+        .oneshot(
+            Request::get("/")
+                .header(header::ACCEPT, "text/html")
+                .body(Body::empty())
+                .unwrap(),
+        )
         .await
         .unwrap();
 
@@ -40,9 +50,10 @@ async fn application_build_successfully_creates_main_and_metrics_servers() {
         .unwrap();
 
     // act
-    let TestApp { port } = TestApp::spawn().await;
+    let TestApp { port, .. } = TestApp::spawn().await;
     let main_server_response = client
         .get(format!("http://localhost:{port}"))
+        .header(header::ACCEPT, "text/html")
         .send()
         .await
         .unwrap();
@@ -50,3 +61,207 @@ async fn application_build_successfully_creates_main_and_metrics_servers() {
     // assert
     assert_eq!(main_server_response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn application_serves_prometheus_metrics_on_admin_listener() {
+    // arrange
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let config = ApplicationConfig {
+        metrics_endpoint: MetricsEndpoint::AdminListener("127.0.0.1:0".parse().unwrap()),
+        ..TestApp::config()
+    };
+    let TestApp { port, metrics_port } = TestApp::spawn_with_config(config).await;
+    let metrics_port = metrics_port.unwrap();
+    client
+        .get(format!("http://localhost:{port}/health"))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let metrics_response = client
+        .get(format!("http://localhost:{metrics_port}/metrics"))
+        .send()
+        .await
+        .unwrap();
+    let main_server_metrics_response = client
+        .get(format!("http://localhost:{port}/metrics"))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(metrics_response.status(), StatusCode::OK);
+    assert_eq!(
+        metrics_response.headers()[header::CONTENT_TYPE],
+        "text/plain; version=0.0.4"
+    );
+    let body = metrics_response.text().await.unwrap();
+    assert!(body.contains(r#"path="/health""#), "{body}");
+    assert_eq!(main_server_metrics_response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn application_records_graphql_operation_metrics() {
+    // arrange
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let config = ApplicationConfig {
+        metrics_endpoint: MetricsEndpoint::AdminListener("127.0.0.1:0".parse().unwrap()),
+        ..TestApp::config()
+    };
+    let TestApp { port, metrics_port } = TestApp::spawn_with_config(config).await;
+    let metrics_port = metrics_port.unwrap();
+    for request_body in [
+        serde_json::json!({ "query": "query HelloQuery { hello }" }),
+        serde_json::json!({ "query": "query TrashQuery { binned: trash { id } }" }),
+        serde_json::json!({ "query": "{ hello }", "operationName": "InjectedQuery" }),
+    ] {
+        client
+            .post(format!("http://localhost:{port}/"))
+            .json(&request_body)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    // act
+    let metrics_response = client
+        .get(format!("http://localhost:{metrics_port}/metrics"))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(metrics_response.status(), StatusCode::OK);
+    let body = metrics_response.text().await.unwrap();
+    assert!(
+        body.lines()
+            .any(|line| line.starts_with("graphql_operations_total")
+                && line.contains(r#"operation_name="HelloQuery""#)
+                && line.contains(r#"operation_type="query""#)),
+        "{body}"
+    );
+    assert!(
+        body.lines().any(
+            |line| line.starts_with("graphql_operation_duration_seconds")
+                && line.contains(r#"operation_name="TrashQuery""#)
+        ),
+        "{body}"
+    );
+    assert!(
+        body.lines()
+            .any(|line| line.starts_with("graphql_resolver_errors_total")
+                && line.contains(r#"operation_name="TrashQuery""#)
+                && line.contains(r#"path="trash""#)),
+        "{body}"
+    );
+    assert!(
+        body.lines()
+            .any(|line| line.starts_with("graphql_operations_total")
+                && line.contains(r#"operation_name="unknown""#)),
+        "{body}"
+    );
+    assert!(!body.contains("InjectedQuery"), "{body}");
+    assert!(!body.contains("binned"), "{body}");
+}
+
+#[tokio::test]
+async fn application_records_database_query_metrics() {
+    // arrange
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+    let config = ApplicationConfig {
+        metrics_endpoint: MetricsEndpoint::AdminListener("127.0.0.1:0".parse().unwrap()),
+        ..TestApp::config()
+    };
+    let TestApp { port, metrics_port } = TestApp::spawn_with_config(config).await;
+    let metrics_port = metrics_port.unwrap();
+    client
+        .post(format!("http://localhost:{port}/"))
+        .json(&serde_json::json!({ "query": "query PostsQuery { posts { nodes { id } } }" }))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("http://localhost:{port}/"))
+        .header(header::AUTHORIZATION, TestApp::authorization_header())
+        .json(&serde_json::json!({ "query": r#"mutation { createUser(name: "Ada") { id } }"# }))
+        .send()
+        .await
+        .unwrap();
+
+    // act
+    let metrics_response = client
+        .get(format!("http://localhost:{metrics_port}/metrics"))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(metrics_response.status(), StatusCode::OK);
+    let body = metrics_response.text().await.unwrap();
+    assert!(
+        body.lines()
+            .any(|line| line.starts_with("db_query_duration_seconds_count")
+                && line.contains(r#"query="Posts query""#)
+                && line.contains(r#"operation="SELECT""#)),
+        "{body}"
+    );
+    assert!(
+        body.lines()
+            .any(|line| line.starts_with("db_query_duration_seconds_count")
+                && line.contains(r#"query="Create user mutation""#)
+                && line.contains(r#"operation="INSERT""#)),
+        "{body}"
+    );
+    for gauge in [
+        "db_pool_size",
+        "db_pool_idle_connections",
+        "db_pool_acquire_wait_seconds",
+    ] {
+        assert!(
+            body.lines().any(|line| line.starts_with(gauge)),
+            "{gauge} missing from {body}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn application_continues_trace_from_traceparent_header() {
+    // arrange
+    let TestApp { port, .. } = TestApp::spawn().await;
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(1))
+        .build()
+        .unwrap();
+
+    // act
+    let response = client
+        .post(format!("http://localhost:{port}/"))
+        .header(
+            "traceparent",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        )
+        .header("tracestate", "gateway=00f067aa0ba902b7")
+        .header("baggage", "tenant=blog")
+        .json(&serde_json::json!({ "query": "query HelloQuery { hello }" }))
+        .send()
+        .await
+        .unwrap();
+
+    // assert
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body["extensions"]["traceId"],
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+}
@@ -0,0 +1,5 @@
+#[cfg(test)]
+mod scheduled_publish;
+
+#[cfg(test)]
+mod trash_purge;
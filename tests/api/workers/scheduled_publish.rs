@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use axum_graphql::{
+    model::{
+        events::{PostEvent, PostEventBroker},
+        post::{Post, create_draft_mutation, post_query},
+        schedule::schedule_publish_mutation,
+    },
+    workers::scheduled_publish::{ScheduledPublishConfig, run_scheduled_publish},
+};
+use chrono::{TimeDelta, Utc};
+use tokio::sync::watch;
+use tokio_stream::StreamExt;
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn run_scheduled_publish_catches_up_on_overdue_drafts_on_start() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Overdue Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    schedule_publish_mutation(&db_pool, id, Utc::now() + TimeDelta::milliseconds(50))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let events = PostEventBroker::default();
+    let mut stream = Box::pin(events.subscribe());
+    let config = ScheduledPublishConfig {
+        interval: Duration::from_secs(3_600),
+    };
+    let (stop, stopped) = watch::channel(false);
+
+    // act
+    let task = tokio::spawn(run_scheduled_publish(
+        db_pool.clone(),
+        events.clone(),
+        config,
+        stopped,
+    ));
+    let event = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap();
+    stop.send_replace(true);
+    task.await.unwrap();
+
+    // assert
+    let Some(PostEvent::Published(post)) = event else {
+        panic!("expected published event");
+    };
+    assert_eq!(post.id, id);
+    let post = post_query(&db_pool, id).await.unwrap().unwrap();
+    assert!(post.published);
+    assert_eq!(post.scheduled_at, None);
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use axum_graphql::{
+    model::post::{Post, create_draft_mutation, delete_draft_mutation, trash_query},
+    workers::trash_purge::{TrashPurgeConfig, run_trash_purge},
+};
+use chrono::TimeDelta;
+use tokio::sync::watch;
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn run_trash_purge_purges_expired_drafts_on_start() {
+    // arrange
+    let db_pool = TestApp::get_db_pool().await;
+    let Post { id, .. } = create_draft_mutation(&db_pool, "Deleted Draft", "Draft body.", None)
+        .await
+        .unwrap();
+    delete_draft_mutation(&db_pool, id).await.unwrap();
+    let config = TrashPurgeConfig {
+        retention: TimeDelta::zero(),
+        interval: Duration::from_secs(3_600),
+    };
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let (stop, stopped) = watch::channel(false);
+
+    // act
+    let task = tokio::spawn(run_trash_purge(db_pool.clone(), config, stopped));
+    let mut trash = trash_query(&db_pool).await.unwrap();
+    for _ in 0..100 {
+        if trash.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        trash = trash_query(&db_pool).await.unwrap();
+    }
+    stop.send_replace(true);
+    task.await.unwrap();
+
+    // assert
+    assert_eq!(trash, Vec::<Post>::new());
+}